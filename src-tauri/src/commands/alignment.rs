@@ -0,0 +1,372 @@
+use super::transcribe::Word;
+use super::vad::calibrate_thresholds;
+
+// ── Energy-based word alignment ──
+//
+// Moonshine only returns text, so word boundaries are recovered from the audio:
+// words are laid over the voiced frames in proportion to their length, then each
+// boundary between two touching words is snapped to the nearby energy minimum
+// (or to the edges of a pause when the minimum falls in one).
+
+/// Analysis frame length
+const FRAME_SECS: f64 = 0.01;
+/// How far (in frames) a boundary may move when snapping to an energy minimum
+const SNAP_RADIUS_FRAMES: usize = 15;
+/// Shortest span (in frames) a word can be squeezed to while snapping
+const MIN_WORD_FRAMES: usize = 5;
+/// Below this speech/noise ratio the clip is treated as entirely voiced (6 dB)
+const MIN_SPEECH_TO_NOISE: f32 = 2.0;
+
+fn frame_energies(samples: &[f32], frame_len: usize) -> Vec<f32> {
+    samples
+        .chunks(frame_len)
+        .map(|frame| (frame.iter().map(|s| s * s).sum::<f32>() / frame.len() as f32).sqrt())
+        .collect()
+}
+
+/// Relative length of a word when spreading it over voiced time. Letters roughly
+/// track phones; the constant covers onset/offset transitions of short words.
+fn word_weight(word: &str) -> f64 {
+    word.chars().filter(|c| c.is_alphanumeric()).count() as f64 + 1.0
+}
+
+/// Align `words` (in spoken order) to `samples`.
+/// Returns one (start, end) pair per word, in seconds from the first sample.
+pub(crate) fn align_words(words: &[&str], samples: &[f32], sample_rate: u32) -> Vec<(f64, f64)> {
+    if words.is_empty() {
+        return Vec::new();
+    }
+    let frame_len = ((sample_rate as f64 * FRAME_SECS) as usize).max(1);
+    let energies = frame_energies(samples, frame_len);
+    if energies.is_empty() {
+        return vec![(0.0, 0.0); words.len()];
+    }
+
+    let thresholds = calibrate_thresholds(&energies);
+    let all_voiced = thresholds.speech_level < thresholds.noise_floor * MIN_SPEECH_TO_NOISE;
+    let voiced: Vec<bool> = energies.iter().map(|&e| all_voiced || e >= thresholds.offset).collect();
+    let mut voiced_idx: Vec<usize> = (0..energies.len()).filter(|&i| voiced[i]).collect();
+    if voiced_idx.is_empty() {
+        voiced_idx = (0..energies.len()).collect();
+    }
+
+    // Spread words over cumulative voiced time (spans are [start, end) in frames)
+    let weights: Vec<f64> = words.iter().map(|w| word_weight(w)).collect();
+    let total_weight: f64 = weights.iter().sum();
+    let n_voiced = voiced_idx.len();
+    let mut spans: Vec<(usize, usize)> = Vec::with_capacity(words.len());
+    let mut cumulative = 0.0;
+    let mut prev_end = 0;
+    for weight in &weights {
+        let a = ((cumulative / total_weight * n_voiced as f64).round() as usize).min(n_voiced - 1);
+        cumulative += weight;
+        let b = ((cumulative / total_weight * n_voiced as f64).round() as usize).clamp(a + 1, n_voiced);
+        let start = voiced_idx[a].max(prev_end);
+        let end = (voiced_idx[b - 1] + 1).max(start);
+        spans.push((start, end));
+        prev_end = end;
+    }
+
+    // Move each boundary between touching words to the quietest nearby frame
+    for i in 0..spans.len() - 1 {
+        let boundary = spans[i].1;
+        if boundary < spans[i + 1].0 {
+            continue; // already separated by a pause
+        }
+        let lo = (spans[i].0 + MIN_WORD_FRAMES).max(boundary.saturating_sub(SNAP_RADIUS_FRAMES));
+        let hi = spans[i + 1].1.saturating_sub(MIN_WORD_FRAMES).min(boundary + SNAP_RADIUS_FRAMES);
+        if lo >= hi {
+            continue;
+        }
+        let quietest = (lo..hi)
+            .min_by(|&a, &b| energies[a].partial_cmp(&energies[b]).unwrap_or(std::cmp::Ordering::Equal))
+            .unwrap_or(boundary);
+
+        if voiced[quietest] {
+            spans[i].1 = quietest;
+            spans[i + 1].0 = quietest;
+        } else {
+            // Landed in a pause: end the word where it starts, begin the next where it ends
+            let mut gap_start = quietest;
+            while gap_start > spans[i].0 + 1 && !voiced[gap_start - 1] {
+                gap_start -= 1;
+            }
+            let mut gap_end = quietest;
+            while gap_end + 1 < spans[i + 1].1 && !voiced[gap_end] {
+                gap_end += 1;
+            }
+            spans[i].1 = gap_start;
+            spans[i + 1].0 = gap_end;
+        }
+    }
+
+    let to_secs = |frame: usize| ((frame * frame_len).min(samples.len())) as f64 / sample_rate as f64;
+    spans.into_iter().map(|(s, e)| (to_secs(s), to_secs(e))).collect()
+}
+
+// ── Script alignment ──
+//
+// A known script is mapped onto the words a recogniser heard: an edit-distance
+// alignment pairs script words with recognised ones, paired words take the
+// recognised timings, and words the recogniser missed are spread over the gap
+// between their aligned neighbours.
+
+/// Confidence for a script word whose recognised counterpart matched exactly
+/// is the recogniser's own; these cover the weaker cases.
+const SUBSTITUTED_CONFIDENCE: f64 = 0.6;
+const INTERPOLATED_CONFIDENCE: f64 = 0.3;
+/// Minimum spelling similarity (0–1) for a mismatched pair to share timing,
+/// e.g. "colour"/"color"; below it the script word is treated as missed
+const MIN_SUBSTITUTION_SIMILARITY: f64 = 0.5;
+/// Half-width of the diagonal band searched by the alignment (in words)
+const ALIGN_BAND: usize = 200;
+/// Upper bound on speaking time per weight unit for words before the first /
+/// after the last aligned word, so they don't stretch over leading/trailing silence
+const EDGE_SECS_PER_WEIGHT: f64 = 0.08;
+
+/// Lowercase letters and digits only, so punctuation and case don't block a match
+fn normalize_token(word: &str) -> String {
+    word.chars().filter(|c| c.is_alphanumeric()).flat_map(|c| c.to_lowercase()).collect()
+}
+
+/// 1 - (character edit distance / longer length)
+fn spelling_similarity(a: &str, b: &str) -> f64 {
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut row = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            row[j + 1] = (prev[j] + usize::from(ca != cb)).min(prev[j + 1] + 1).min(row[j] + 1);
+        }
+        prev = row;
+    }
+    1.0 - prev[b.len()] as f64 / longest as f64
+}
+
+/// Word-level edit-distance alignment of `a` to `b` within a band around the diagonal.
+/// Returns the pairs in order; `None` on one side is an insertion/deletion.
+fn align_sequences(a: &[String], b: &[String]) -> Vec<(Option<usize>, Option<usize>)> {
+    const INF: u32 = u32::MAX / 2;
+    let (n, m) = (a.len(), b.len());
+    let band = ALIGN_BAND + n.abs_diff(m);
+    let center = |i: usize| (i * m + n / 2).checked_div(n).unwrap_or(m);
+    let bounds: Vec<(usize, usize)> = (0..=n)
+        .map(|i| (center(i).saturating_sub(band), (center(i) + band).min(m)))
+        .collect();
+
+    // cost[i][j - lo_i]; step: 0 = diagonal, 1 = skip a[i-1], 2 = skip b[j-1]
+    let mut cost: Vec<Vec<u32>> = Vec::with_capacity(n + 1);
+    let mut step: Vec<Vec<u8>> = Vec::with_capacity(n + 1);
+    let get = |cost: &Vec<Vec<u32>>, i: usize, j: usize| {
+        let (lo, hi) = bounds[i];
+        if j < lo || j > hi { INF } else { cost[i][j - lo] }
+    };
+
+    for (i, &(lo, hi)) in bounds.iter().enumerate() {
+        let mut row_cost = vec![INF; hi - lo + 1];
+        let mut row_step = vec![0u8; hi - lo + 1];
+        for j in lo..=hi {
+            let (c, s) = if i == 0 {
+                (j as u32, 2)
+            } else if j == 0 {
+                (i as u32, 1)
+            } else {
+                let sub = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+                let diag = get(&cost, i - 1, j - 1) + sub;
+                let up = get(&cost, i - 1, j) + 1;
+                let left = if j > lo { row_cost[j - 1 - lo] + 1 } else { INF };
+                if diag <= up && diag <= left {
+                    (diag, 0)
+                } else if up <= left {
+                    (up, 1)
+                } else {
+                    (left, 2)
+                }
+            };
+            row_cost[j - lo] = c;
+            row_step[j - lo] = s;
+        }
+        cost.push(row_cost);
+        step.push(row_step);
+    }
+
+    let mut pairs = Vec::with_capacity(n.max(m));
+    let (mut i, mut j) = (n, m);
+    while i > 0 || j > 0 {
+        match step[i][j - bounds[i].0] {
+            0 => {
+                pairs.push((Some(i - 1), Some(j - 1)));
+                i -= 1;
+                j -= 1;
+            }
+            1 => {
+                pairs.push((Some(i - 1), None));
+                i -= 1;
+            }
+            _ => {
+                pairs.push((None, Some(j - 1)));
+                j -= 1;
+            }
+        }
+    }
+    pairs.reverse();
+    pairs
+}
+
+/// Time `script` against `recognized` words (in time order) from the same audio.
+/// `range` is the (start, end) of the audio the recogniser saw; the returned words
+/// carry the script's exact text.
+pub(crate) fn align_script(script: &str, recognized: &[Word], range: (f64, f64)) -> Vec<Word> {
+    let script_words: Vec<&str> = script.split_whitespace().collect();
+    let script_norm: Vec<String> = script_words.iter().map(|w| normalize_token(w)).collect();
+    let heard_norm: Vec<String> = recognized.iter().map(|w| normalize_token(&w.text)).collect();
+
+    // Timing and confidence for each script word that paired with a recognised word
+    let mut timed: Vec<Option<(f64, f64, f64)>> = vec![None; script_words.len()];
+    for (si, ri) in align_sequences(&script_norm, &heard_norm) {
+        if let (Some(si), Some(ri)) = (si, ri) {
+            let heard = &recognized[ri];
+            if script_norm[si] == heard_norm[ri] {
+                timed[si] = Some((heard.start, heard.end, heard.confidence));
+            } else if spelling_similarity(&script_norm[si], &heard_norm[ri]) >= MIN_SUBSTITUTION_SIMILARITY {
+                timed[si] = Some((heard.start, heard.end, SUBSTITUTED_CONFIDENCE));
+            }
+        }
+    }
+
+    // Spread each run of unpaired words over the gap between its neighbours
+    let mut i = 0;
+    while i < timed.len() {
+        if timed[i].is_some() {
+            i += 1;
+            continue;
+        }
+        let run_end = (i..timed.len()).find(|&k| timed[k].is_some()).unwrap_or(timed.len());
+        let weights: Vec<f64> = script_words[i..run_end].iter().map(|w| word_weight(w)).collect();
+        let total: f64 = weights.iter().sum();
+
+        let prev_end = if i > 0 { timed[i - 1].map(|t| t.1) } else { None };
+        let next_start = timed.get(run_end).copied().flatten().map(|t| t.0);
+        let edge_len = total * EDGE_SECS_PER_WEIGHT;
+        let (gap_start, gap_end) = match (prev_end, next_start) {
+            (Some(p), Some(n)) => (p, n.max(p)),
+            (Some(p), None) => (p, range.1.min(p + edge_len).max(p)),
+            (None, Some(n)) => (range.0.max(n - edge_len).min(n), n),
+            (None, None) => (range.0, range.1.min(range.0 + edge_len)),
+        };
+
+        let mut cursor = gap_start;
+        for (k, weight) in (i..run_end).zip(weights) {
+            let end = if k + 1 == run_end { gap_end } else { cursor + (gap_end - gap_start) * weight / total };
+            timed[k] = Some((cursor, end, INTERPOLATED_CONFIDENCE));
+            cursor = end;
+        }
+        i = run_end;
+    }
+
+    script_words
+        .iter()
+        .zip(timed)
+        .map(|(text, t)| {
+            let (start, end, confidence) = t.unwrap_or((range.0, range.0, INTERPOLATED_CONFIDENCE));
+            Word {
+                id: uuid::Uuid::new_v4().to_string(),
+                text: text.to_string(),
+                start,
+                end,
+                confidence,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SR: u32 = 16000;
+
+    /// Tone bursts described by (start, end, amplitude) over `total` seconds of silence
+    fn bursts(total: f64, parts: &[(f64, f64, f32)]) -> Vec<f32> {
+        let mut samples = vec![0.0f32; (total * SR as f64) as usize];
+        for &(start, end, amp) in parts {
+            let (a, b) = ((start * SR as f64) as usize, (end * SR as f64) as usize);
+            for (i, s) in samples[a..b].iter_mut().enumerate() {
+                *s = amp * (2.0 * std::f32::consts::PI * 220.0 * i as f32 / SR as f32).sin();
+            }
+        }
+        samples
+    }
+
+    #[test]
+    fn test_align_words_to_pauses() {
+        let samples = bursts(2.0, &[(0.2, 0.5, 0.5), (0.7, 1.3, 0.5), (1.5, 1.8, 0.5)]);
+        let spans = align_words(&["aa", "bbbb", "cc"], &samples, SR);
+        let expected = [(0.2, 0.5), (0.7, 1.3), (1.5, 1.8)];
+        assert_eq!(spans.len(), 3);
+        for ((start, end), (want_start, want_end)) in spans.iter().zip(expected.iter()) {
+            assert!((start - want_start).abs() < 0.03, "start {} vs {}", start, want_start);
+            assert!((end - want_end).abs() < 0.03, "end {} vs {}", end, want_end);
+        }
+    }
+
+    #[test]
+    fn test_align_words_snaps_to_energy_dip() {
+        // Continuous speech with a dip at 0.75–0.80s; proportional split would put the boundary at 0.7s
+        let samples = bursts(1.4, &[(0.2, 0.75, 0.5), (0.75, 0.8, 0.05), (0.8, 1.2, 0.5)]);
+        let spans = align_words(&["ab", "ab"], &samples, SR);
+        assert!((spans[0].0 - 0.2).abs() < 0.02);
+        assert!(spans[0].1 >= 0.74 && spans[0].1 <= 0.81, "boundary at {}", spans[0].1);
+        assert_eq!(spans[0].1, spans[1].0);
+        assert!((spans[1].1 - 1.2).abs() < 0.02);
+    }
+
+    #[test]
+    fn test_align_words_empty() {
+        assert!(align_words(&[], &[0.0; 1600], SR).is_empty());
+        assert_eq!(align_words(&["a"], &[], SR), vec![(0.0, 0.0)]);
+    }
+
+    fn heard(words: &[(&str, f64, f64)]) -> Vec<Word> {
+        words
+            .iter()
+            .map(|&(text, start, end)| Word { id: String::new(), text: text.to_string(), start, end, confidence: 0.9 })
+            .collect()
+    }
+
+    #[test]
+    fn test_align_script_keeps_script_text() {
+        let recognized = heard(&[("hello", 0.5, 0.9), ("world", 1.0, 1.4), ("colour", 1.5, 1.9)]);
+        let words = align_script("Hello, world! Color", &recognized, (0.0, 2.0));
+        let texts: Vec<&str> = words.iter().map(|w| w.text.as_str()).collect();
+        assert_eq!(texts, vec!["Hello,", "world!", "Color"]);
+        assert_eq!((words[0].start, words[0].end), (0.5, 0.9));
+        assert_eq!(words[0].confidence, 0.9);
+        // Misheard word keeps the recognised timing at lower confidence
+        assert_eq!((words[2].start, words[2].end), (1.5, 1.9));
+        assert_eq!(words[2].confidence, SUBSTITUTED_CONFIDENCE);
+    }
+
+    #[test]
+    fn test_align_script_interpolates_missed_words() {
+        // Recogniser dropped "quick brown" and added a stray "um"
+        let recognized = heard(&[("the", 0.0, 0.2), ("um", 0.2, 0.3), ("fox", 1.0, 1.3)]);
+        let words = align_script("the quick brown fox", &recognized, (0.0, 2.0));
+        assert_eq!(words.len(), 4);
+        assert_eq!(words[3].start, 1.0);
+        assert!(words[1].start >= 0.2 && words[2].end <= 1.0);
+        assert!(words[1].end <= words[2].start + 1e-9);
+        assert_eq!(words[1].confidence, INTERPOLATED_CONFIDENCE);
+    }
+
+    #[test]
+    fn test_align_sequences_identical() {
+        let a: Vec<String> = ["a", "b", "c"].iter().map(|s| s.to_string()).collect();
+        let pairs = align_sequences(&a, &a);
+        assert_eq!(pairs, vec![(Some(0), Some(0)), (Some(1), Some(1)), (Some(2), Some(2))]);
+    }
+}
@@ -0,0 +1,526 @@
+//! Bounded-memory long-form audio: streaming 16 kHz decode and overlapping
+//! transcription windows cut at quiet points.
+//!
+//! Each window owns the span between its two cut points; the audio around the
+//! cuts is shared with the neighbouring windows as context. Words are kept by
+//! the window that owns their midpoint, so overlaps are decoded twice but
+//! stitched once.
+
+use std::fs::File;
+use std::path::Path;
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{Decoder, DecoderOptions};
+use symphonia::core::formats::{FormatOptions, FormatReader, SeekMode, SeekTo};
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+use symphonia::core::units::Time;
+
+use super::transcribe::Word;
+
+pub(crate) const SAMPLE_RATE: usize = 16000;
+/// Frame used to find the quietest cut point (20ms)
+const CUT_FRAME: usize = 320;
+
+/// Something that yields 16 kHz mono samples in order
+pub(crate) trait SampleSource {
+    /// Append up to `max` samples to `out`; returns how many were appended (0 = end)
+    fn read(&mut self, max: usize, out: &mut Vec<f32>) -> Result<usize, String>;
+}
+
+/// In-memory samples (already 16 kHz mono)
+pub(crate) struct SliceSource<'a> {
+    samples: &'a [f32],
+    pos: usize,
+}
+
+impl<'a> SliceSource<'a> {
+    pub fn new(samples: &'a [f32]) -> Self {
+        Self { samples, pos: 0 }
+    }
+}
+
+impl SampleSource for SliceSource<'_> {
+    fn read(&mut self, max: usize, out: &mut Vec<f32>) -> Result<usize, String> {
+        let end = (self.pos + max).min(self.samples.len());
+        out.extend_from_slice(&self.samples[self.pos..end]);
+        let n = end - self.pos;
+        self.pos = end;
+        Ok(n)
+    }
+}
+
+/// Downmixes to mono and linearly resamples to 16 kHz. Source audio is pushed in as it
+/// is decoded or received; resampled audio is pulled out once it can be interpolated.
+pub(crate) struct Resampler16k {
+    /// Source samples per output sample
+    step: f64,
+    /// Pending source-rate mono samples
+    src: Vec<f32>,
+    /// Fractional read position in `src`
+    pos: f64,
+}
+
+impl Resampler16k {
+    pub fn new(sample_rate: f64) -> Self {
+        Self { step: sample_rate / SAMPLE_RATE as f64, src: Vec::new(), pos: 0.0 }
+    }
+
+    /// Append one interleaved frame, downmixed to mono
+    pub fn push_frame(&mut self, frame: &[f32]) {
+        self.src.push(frame.iter().sum::<f32>() / frame.len().max(1) as f32);
+    }
+
+    /// Append interleaved audio with `channels` channels
+    pub fn push(&mut self, interleaved: &[f32], channels: usize) {
+        for frame in interleaved.chunks(channels.max(1)) {
+            self.push_frame(frame);
+        }
+    }
+
+    /// Append up to `max` resampled samples to `out`; returns how many
+    pub fn resample(&mut self, max: usize, out: &mut Vec<f32>) -> usize {
+        let mut produced = 0;
+        // Interpolate while both neighbours of the read position are buffered
+        while produced < max && (self.pos as usize) + 1 < self.src.len() {
+            let idx = self.pos as usize;
+            let frac = (self.pos - idx as f64) as f32;
+            out.push(self.src[idx] * (1.0 - frac) + self.src[idx + 1] * frac);
+            produced += 1;
+            self.pos += self.step;
+        }
+        // Drop consumed source samples, keeping the one we interpolate from
+        let consumed = (self.pos as usize).min(self.src.len());
+        self.src.drain(..consumed);
+        self.pos -= consumed as f64;
+        produced
+    }
+
+    /// At the end of the stream: append up to `max` of the last source samples
+    pub fn flush(&mut self, max: usize, out: &mut Vec<f32>) -> usize {
+        let mut produced = 0;
+        while produced < max && (self.pos as usize) < self.src.len() {
+            out.push(self.src[self.pos as usize]);
+            produced += 1;
+            self.pos += self.step;
+        }
+        produced
+    }
+}
+
+/// Decodes a file packet by packet, downmixing to mono and linearly resampling to 16 kHz.
+/// Only the current packet is held in memory.
+pub(crate) struct Stream16k {
+    format: Box<dyn FormatReader>,
+    decoder: Box<dyn Decoder>,
+    track_id: u32,
+    channels: usize,
+    resampler: Resampler16k,
+    eof: bool,
+    duration_secs: Option<f64>,
+    /// Source frames still to drop before the requested start
+    skip_frames: usize,
+    /// Source frames left before the requested end (None = to end of file)
+    remaining_frames: Option<usize>,
+}
+
+impl Stream16k {
+    pub fn open(path: &Path) -> Result<Self, String> {
+        Self::open_range(path, None, None)
+    }
+
+    /// Stream only `[start_time, end_time)` of the file (seconds; None = file start / end)
+    pub fn open_range(path: &Path, start_time: Option<f64>, end_time: Option<f64>) -> Result<Self, String> {
+        let start = start_time.unwrap_or(0.0);
+        if start < 0.0 {
+            return Err(format!("Invalid start time: {}", start));
+        }
+        if let Some(end) = end_time {
+            if end <= start {
+                return Err(format!("Invalid time range: {:.3}s - {:.3}s", start, end));
+            }
+        }
+        let file = File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+        let mss = MediaSourceStream::new(Box::new(file), Default::default());
+
+        let mut hint = Hint::new();
+        if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+            hint.with_extension(ext);
+        }
+
+        let probed = symphonia::default::get_probe()
+            .format(&hint, mss, &FormatOptions::default(), &MetadataOptions::default())
+            .map_err(|e| format!("Failed to probe format: {}", e))?;
+        let mut format = probed.format;
+
+        let track = format
+            .tracks()
+            .iter()
+            .find(|t| t.codec_params.codec != symphonia::core::codecs::CODEC_TYPE_NULL)
+            .ok_or("No audio tracks found")?;
+
+        let track_id = track.id;
+        let sample_rate = track.codec_params.sample_rate.unwrap_or(44100) as f64;
+        let channels = track.codec_params.channels.map(|c| c.count()).unwrap_or(2).max(1);
+        let time_base = track.codec_params.time_base;
+        let file_duration = track.codec_params.n_frames.map(|n| n as f64 / sample_rate);
+
+        let mut decoder = symphonia::default::get_codecs()
+            .make(&track.codec_params, &DecoderOptions::default())
+            .map_err(|e| format!("Failed to create decoder: {}", e))?;
+
+        // Seek to the packet containing `start`, then drop the frames before it.
+        // Formats that can't seek are decoded from the beginning and skipped instead.
+        let mut skip_frames = (start * sample_rate) as usize;
+        if start > 0.0 {
+            let seek = format.seek(
+                SeekMode::Accurate,
+                SeekTo::Time { time: Time::from(start), track_id: Some(track_id) },
+            );
+            if let Ok(seeked) = seek {
+                decoder.reset();
+                let gap_ts = seeked.required_ts.saturating_sub(seeked.actual_ts);
+                skip_frames = match time_base {
+                    Some(tb) => {
+                        let t = tb.calc_time(gap_ts);
+                        ((t.seconds as f64 + t.frac) * sample_rate).round() as usize
+                    }
+                    None => gap_ts as usize,
+                };
+            }
+        }
+
+        let remaining_frames = end_time.map(|end| ((end - start) * sample_rate) as usize);
+        let duration_secs = match (end_time, file_duration) {
+            (Some(end), Some(total)) => Some(end.min(total) - start),
+            (Some(end), None) => Some(end - start),
+            (None, total) => total.map(|t| (t - start).max(0.0)),
+        };
+
+        Ok(Self {
+            format,
+            decoder,
+            track_id,
+            channels,
+            resampler: Resampler16k::new(sample_rate),
+            eof: false,
+            duration_secs,
+            skip_frames,
+            remaining_frames,
+        })
+    }
+
+    /// Duration of the streamed range, if the container declares the file length
+    pub fn duration_secs(&self) -> Option<f64> {
+        self.duration_secs
+    }
+
+    /// Decode the next packet of our track into the resampler; sets `eof` when the stream ends
+    fn decode_next(&mut self) {
+        if self.remaining_frames == Some(0) {
+            self.eof = true;
+            return;
+        }
+        loop {
+            let packet = match self.format.next_packet() {
+                Ok(packet) => packet,
+                Err(_) => {
+                    self.eof = true;
+                    return;
+                }
+            };
+            if packet.track_id() != self.track_id {
+                continue;
+            }
+            let decoded = match self.decoder.decode(&packet) {
+                Ok(decoded) => decoded,
+                Err(_) => continue,
+            };
+
+            let spec = *decoded.spec();
+            let mut sample_buf = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+            sample_buf.copy_interleaved_ref(decoded);
+            for frame in sample_buf.samples().chunks(self.channels) {
+                if self.skip_frames > 0 {
+                    self.skip_frames -= 1;
+                    continue;
+                }
+                if let Some(remaining) = self.remaining_frames.as_mut() {
+                    if *remaining == 0 {
+                        break;
+                    }
+                    *remaining -= 1;
+                }
+                self.resampler.push_frame(frame);
+            }
+            return;
+        }
+    }
+}
+
+impl SampleSource for Stream16k {
+    fn read(&mut self, max: usize, out: &mut Vec<f32>) -> Result<usize, String> {
+        let mut produced = 0;
+        while produced < max {
+            produced += self.resampler.resample(max - produced, out);
+            if produced >= max {
+                break;
+            }
+            if self.eof {
+                // Flush the last source sample
+                produced += self.resampler.flush(max - produced, out);
+                break;
+            }
+            self.decode_next();
+        }
+        Ok(produced)
+    }
+}
+
+/// Window sizing for long-form transcription (all in seconds)
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct WindowConfig {
+    /// Maximum window length
+    pub window: f64,
+    /// Context shared with the previous window before its cut point
+    pub overlap: f64,
+    /// How far back from the window end to look for a quiet cut point
+    pub search: f64,
+}
+
+impl WindowConfig {
+    fn samples(secs: f64) -> usize {
+        (secs * SAMPLE_RATE as f64) as usize
+    }
+}
+
+/// One transcription window
+#[derive(Debug, Clone)]
+pub(crate) struct AudioWindow {
+    /// Absolute start time of `samples[0]`
+    pub start: f64,
+    pub samples: Vec<f32>,
+    /// Absolute time range whose words this window keeps
+    pub keep_from: f64,
+    pub keep_to: f64,
+}
+
+impl AudioWindow {
+    pub fn duration(&self) -> f64 {
+        self.samples.len() as f64 / SAMPLE_RATE as f64
+    }
+
+    /// Whether a word spanning `[start, end]` (absolute) belongs to this window
+    pub fn owns(&self, start: f64, end: f64) -> bool {
+        let mid = (start + end) / 2.0;
+        mid >= self.keep_from && mid < self.keep_to
+    }
+}
+
+/// Splits a sample stream into overlapping windows cut at the quietest point near each window end
+pub(crate) struct Windower<S: SampleSource> {
+    source: S,
+    window: usize,
+    overlap: usize,
+    search: usize,
+    /// Samples from `buffer_start` on, at most one window
+    buffer: Vec<f32>,
+    buffer_start: usize,
+    /// Absolute sample where the next window's owned range starts
+    owned_from: usize,
+    /// Time of the source's first sample, added to every window time
+    offset: f64,
+    eof: bool,
+    done: bool,
+}
+
+impl<S: SampleSource> Windower<S> {
+    pub fn new(source: S, config: WindowConfig) -> Self {
+        let window = WindowConfig::samples(config.window).max(CUT_FRAME * 4);
+        // The next window must start after this one did, or we'd never advance
+        let search = WindowConfig::samples(config.search).min(window / 2);
+        let overlap = WindowConfig::samples(config.overlap).min(window - search - 1);
+        Self {
+            source,
+            window,
+            overlap,
+            search,
+            buffer: Vec::with_capacity(window),
+            buffer_start: 0,
+            owned_from: 0,
+            offset: 0.0,
+            eof: false,
+            done: false,
+        }
+    }
+
+    /// Report window times relative to `offset` seconds (for sources that start mid-file)
+    pub fn starting_at(mut self, offset: f64) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    pub fn next_window(&mut self) -> Result<Option<AudioWindow>, String> {
+        if self.done {
+            return Ok(None);
+        }
+        while self.buffer.len() < self.window && !self.eof {
+            let want = self.window - self.buffer.len();
+            if self.source.read(want, &mut self.buffer)? == 0 {
+                self.eof = true;
+            }
+        }
+        if self.buffer.is_empty() {
+            self.done = true;
+            return Ok(None);
+        }
+
+        let offset = self.offset;
+        let to_secs = |s: usize| offset + s as f64 / SAMPLE_RATE as f64;
+        let start = to_secs(self.buffer_start);
+        let keep_from = to_secs(self.owned_from);
+
+        if self.eof {
+            self.done = true;
+            return Ok(Some(AudioWindow {
+                start,
+                samples: std::mem::take(&mut self.buffer),
+                keep_from,
+                keep_to: f64::INFINITY,
+            }));
+        }
+
+        let cut = find_quiet_point(&self.buffer, self.window - self.search, self.window);
+        let window = AudioWindow {
+            start,
+            samples: self.buffer.clone(),
+            keep_from,
+            keep_to: to_secs(self.buffer_start + cut),
+        };
+
+        // Next window starts `overlap` before the cut for context
+        let advance = cut.saturating_sub(self.overlap).max(1);
+        self.buffer.drain(..advance);
+        self.buffer_start += advance;
+        self.owned_from = self.buffer_start + (cut - advance);
+        Ok(Some(window))
+    }
+}
+
+/// Centre of the lowest-energy `CUT_FRAME` in `samples[from..to]`
+fn find_quiet_point(samples: &[f32], from: usize, to: usize) -> usize {
+    let to = to.min(samples.len());
+    if to <= from + CUT_FRAME {
+        return to;
+    }
+    (from..to - CUT_FRAME)
+        .step_by(CUT_FRAME / 2)
+        .map(|s| (s, samples[s..s + CUT_FRAME].iter().map(|x| x * x).sum::<f32>()))
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+        .map(|(s, _)| s + CUT_FRAME / 2)
+        .unwrap_or(to)
+}
+
+/// Keep the words a window owns (by midpoint), so overlaps aren't duplicated
+pub(crate) fn keep_owned(window: &AudioWindow, words: Vec<Word>) -> Vec<Word> {
+    words.into_iter().filter(|w| window.owns(w.start, w.end)).collect()
+}
+
+/// Last `max_chars` of `text`, starting at a word boundary (for prompting the next window)
+pub(crate) fn text_tail(text: &str, max_chars: usize) -> &str {
+    if text.len() <= max_chars {
+        return text.trim();
+    }
+    let mut start = text.len() - max_chars;
+    while !text.is_char_boundary(start) {
+        start += 1;
+    }
+    let tail = &text[start..];
+    match tail.find(' ') {
+        Some(space) => tail[space..].trim(),
+        None => tail.trim(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::transcribe::test_word;
+
+    #[test]
+    fn test_windows_cut_in_silence_and_tile_ownership() {
+        // 10s of tone with a 200ms gap at 3.5s and 6.8s
+        let mut samples: Vec<f32> = (0..10 * SAMPLE_RATE).map(|i| ((i % 40) as f32 / 20.0 - 1.0) * 0.5).collect();
+        for gap in [3.5, 6.8] {
+            let s = (gap * SAMPLE_RATE as f64) as usize;
+            samples[s..s + 3200].iter_mut().for_each(|x| *x = 0.0);
+        }
+
+        let config = WindowConfig { window: 4.0, overlap: 0.5, search: 1.0 };
+        let mut windower = Windower::new(SliceSource::new(&samples), config);
+        let mut windows = Vec::new();
+        while let Some(w) = windower.next_window().unwrap() {
+            windows.push(w);
+        }
+
+        assert_eq!(windows.len(), 3);
+        assert_eq!(windows[0].keep_from, 0.0);
+        // Cuts land inside the gaps
+        assert!(windows[0].keep_to > 3.5 && windows[0].keep_to < 3.7);
+        assert!(windows[1].keep_to > 6.8 && windows[1].keep_to < 7.0);
+        // Owned ranges tile, and each window starts `overlap` before its owned range
+        assert_eq!(windows[1].keep_from, windows[0].keep_to);
+        assert_eq!(windows[2].keep_from, windows[1].keep_to);
+        assert!((windows[1].keep_from - windows[1].start - 0.5).abs() < 1e-3);
+        assert_eq!(windows[2].keep_to, f64::INFINITY);
+        // Last window reaches the end of the audio
+        let last = &windows[2];
+        assert!((last.start + last.duration() - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_windower_offset_gives_absolute_times() {
+        let samples = vec![0.1f32; 3 * SAMPLE_RATE];
+        let config = WindowConfig { window: 30.0, overlap: 1.0, search: 5.0 };
+        let mut windower = Windower::new(SliceSource::new(&samples), config).starting_at(42.0);
+        let window = windower.next_window().unwrap().unwrap();
+        assert_eq!(window.start, 42.0);
+        assert_eq!(window.keep_from, 42.0);
+        assert!(window.owns(43.0, 43.5));
+        assert!(!window.owns(10.0, 10.5));
+        assert!(windower.next_window().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_resampler_downmixes_and_resamples() {
+        // 48 kHz stereo, left = 1.0, right = 0.0, pushed in uneven chunks
+        let frames: Vec<f32> = (0..4800).flat_map(|_| [1.0f32, 0.0]).collect();
+        let mut resampler = Resampler16k::new(48000.0);
+        let mut out = Vec::new();
+        for chunk in frames.chunks(322) {
+            resampler.push(chunk, 2);
+            resampler.resample(usize::MAX, &mut out);
+        }
+        resampler.flush(usize::MAX, &mut out);
+        assert_eq!(out.len(), 1600);
+        assert!(out.iter().all(|s| (s - 0.5).abs() < 1e-6));
+    }
+
+    #[test]
+    fn test_keep_owned_dedupes_overlap() {
+        let a = AudioWindow { start: 0.0, samples: Vec::new(), keep_from: 0.0, keep_to: 5.0 };
+        let b = AudioWindow { start: 4.0, samples: Vec::new(), keep_from: 5.0, keep_to: f64::INFINITY };
+        // A word straddling the cut appears in both windows' output
+        let straddler = || test_word("", "w", 4.8, 5.4);
+        let from_a = keep_owned(&a, vec![test_word("", "w", 1.0, 1.5), straddler()]);
+        let from_b = keep_owned(&b, vec![straddler(), test_word("", "w", 6.0, 6.5)]);
+        assert_eq!(from_a.len() + from_b.len(), 3);
+        assert_eq!(from_b.len(), 2);
+    }
+
+    #[test]
+    fn test_text_tail_starts_at_word() {
+        assert_eq!(text_tail("short text", 50), "short text");
+        assert_eq!(text_tail("the quick brown fox", 8), "fox");
+    }
+}
@@ -0,0 +1,410 @@
+//! Speech / music / noise classification of audio regions
+//!
+//! Heuristic classifier over 16 kHz mono. Per-frame energy, spectral flux,
+//! spectral flatness and harmonicity (normalised autocorrelation peak in the
+//! 60-500 Hz pitch range) are summarised over 1s windows, labelled by rules,
+//! smoothed, and merged into regions.
+
+use realfft::num_complex::Complex;
+use realfft::RealFftPlanner;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Instant;
+
+use super::vad::{calibrate_thresholds, from_db, to_db};
+
+const SAMPLE_RATE: usize = 16000;
+/// 32ms analysis frame / 16ms hop
+const FRAME: usize = 512;
+const HOP: usize = 256;
+/// Zero-padded FFT size so the autocorrelation doesn't wrap around
+const FFT: usize = 1024;
+/// Pitch search range for harmonicity (500 Hz down to 60 Hz)
+const MIN_LAG: usize = SAMPLE_RATE / 500;
+const MAX_LAG: usize = SAMPLE_RATE / 60;
+
+/// Classification window (~1s) and hop (~0.5s) in frames
+const WINDOW_FRAMES: usize = 62;
+const WINDOW_HOP_FRAMES: usize = 31;
+
+/// Silence threshold bounds (dBFS); the threshold itself tracks the file's noise floor
+const SILENCE_MIN_DB: f32 = -60.0;
+const SILENCE_MAX_DB: f32 = -40.0;
+/// Coefficient of variation of frame energy above which a window looks syllabic
+const SPEECH_MODULATION: f32 = 0.5;
+/// Fraction of frames below half the window's mean energy above which a window looks syllabic
+const SPEECH_LOW_ENERGY_RATIO: f32 = 0.3;
+/// Mean harmonicity above which sustained sound counts as music
+const MUSIC_HARMONICITY: f32 = 0.45;
+/// Mean normalised spectral flux above which non-harmonic, non-flat sound counts as (percussive) music
+const MUSIC_FLUX: f32 = 0.35;
+/// Spectral flatness above which sound is noise-like
+const NOISE_FLATNESS: f32 = 0.3;
+/// Harmonicity below which flat-spectrum sound is noise
+const NOISE_HARMONICITY: f32 = 0.3;
+/// Default minimum region length; shorter regions are absorbed by a neighbour
+const DEFAULT_MIN_REGION_DURATION: f64 = 1.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RegionClass {
+    Speech,
+    Music,
+    SpeechOverMusic,
+    Noise,
+    Silence,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassifiedRegion {
+    pub start: f64,
+    pub end: f64,
+    pub class: RegionClass,
+}
+
+fn default_min_region_duration() -> f64 {
+    DEFAULT_MIN_REGION_DURATION
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassifyOptions {
+    /// Regions shorter than this (seconds) are merged into a neighbour
+    #[serde(default = "default_min_region_duration")]
+    pub min_region_duration: f64,
+}
+
+impl Default for ClassifyOptions {
+    fn default() -> Self {
+        Self {
+            min_region_duration: DEFAULT_MIN_REGION_DURATION,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassificationResult {
+    pub regions: Vec<ClassifiedRegion>,
+    pub duration: f64,
+    pub processing_time_ms: u64,
+}
+
+/// Per-frame features
+#[derive(Debug, Clone, Copy, Default)]
+struct FrameFeatures {
+    rms: f32,
+    flux: f32,
+    flatness: f32,
+    harmonicity: f32,
+}
+
+fn analyze_frames(samples: &[f32]) -> Vec<FrameFeatures> {
+    if samples.len() < FRAME {
+        return Vec::new();
+    }
+    let n_frames = (samples.len() - FRAME) / HOP + 1;
+
+    let mut planner = RealFftPlanner::<f32>::new();
+    let fft = planner.plan_fft_forward(FFT);
+    let ifft = planner.plan_fft_inverse(FFT);
+    let mut input = fft.make_input_vec();
+    let mut spectrum = fft.make_output_vec();
+    let mut acf = ifft.make_output_vec();
+
+    let window: Vec<f32> = (0..FRAME)
+        .map(|i| 0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / (FRAME - 1) as f32).cos())
+        .collect();
+
+    // Autocorrelation of the window itself, to undo its taper (Boersma's correction)
+    input.iter_mut().for_each(|x| *x = 0.0);
+    input[..FRAME].copy_from_slice(&window);
+    let mut window_acf = vec![0.0f32; FFT];
+    if fft.process(&mut input, &mut spectrum).is_ok() {
+        spectrum.iter_mut().for_each(|c| *c = Complex::new(c.norm_sqr(), 0.0));
+        if ifft.process(&mut spectrum, &mut window_acf).is_err() {
+            window_acf.iter_mut().for_each(|x| *x = 1.0);
+        }
+    }
+
+    // Flatness over 100 Hz - 7 kHz, where both speech and music live
+    let lo_bin = 100 * FFT / SAMPLE_RATE;
+    let hi_bin = 7000 * FFT / SAMPLE_RATE;
+
+    let mut features = Vec::with_capacity(n_frames);
+    let mut prev_mag: Vec<f32> = vec![0.0; FFT / 2 + 1];
+
+    for f in 0..n_frames {
+        let frame = &samples[f * HOP..f * HOP + FRAME];
+        let rms = (frame.iter().map(|s| s * s).sum::<f32>() / FRAME as f32).sqrt();
+
+        input.iter_mut().for_each(|x| *x = 0.0);
+        for (i, (&s, &w)) in frame.iter().zip(&window).enumerate() {
+            input[i] = s * w;
+        }
+        if fft.process(&mut input, &mut spectrum).is_err() {
+            features.push(FrameFeatures { rms, ..Default::default() });
+            continue;
+        }
+
+        let mag: Vec<f32> = spectrum.iter().map(|c| c.norm()).collect();
+        let mag_sum: f32 = mag.iter().sum();
+        let flux = if mag_sum > 1e-9 {
+            mag.iter().zip(&prev_mag).map(|(m, p)| (m - p).max(0.0)).sum::<f32>() / mag_sum
+        } else {
+            0.0
+        };
+
+        let band = &mag[lo_bin..hi_bin];
+        let power_mean = band.iter().map(|m| m * m).sum::<f32>() / band.len() as f32;
+        let log_mean = band.iter().map(|m| (m * m).max(1e-12).ln()).sum::<f32>() / band.len() as f32;
+        let flatness = if power_mean > 1e-12 { log_mean.exp() / power_mean } else { 0.0 };
+
+        // Autocorrelation = inverse FFT of the power spectrum
+        spectrum.iter_mut().for_each(|c| *c = Complex::new(c.norm_sqr(), 0.0));
+        let harmonicity = if ifft.process(&mut spectrum, &mut acf).is_ok() && acf[0] > 1e-9 {
+            (MIN_LAG..=MAX_LAG)
+                .map(|lag| (acf[lag] / acf[0]) / (window_acf[lag] / window_acf[0]).max(1e-3))
+                .fold(0.0f32, f32::max)
+                .min(1.0)
+        } else {
+            0.0
+        };
+
+        features.push(FrameFeatures { rms, flux, flatness, harmonicity });
+        prev_mag = mag;
+    }
+
+    features
+}
+
+fn mean(values: impl Iterator<Item = f32>) -> f32 {
+    let (sum, n) = values.fold((0.0f32, 0usize), |(s, n), v| (s + v, n + 1));
+    if n == 0 { 0.0 } else { sum / n as f32 }
+}
+
+/// Label one window of frames
+fn classify_window(frames: &[FrameFeatures], silence_threshold: f32) -> RegionClass {
+    let rms_mean = mean(frames.iter().map(|f| f.rms));
+    if rms_mean < silence_threshold {
+        return RegionClass::Silence;
+    }
+
+    let audible: Vec<&FrameFeatures> = frames.iter().filter(|f| f.rms >= silence_threshold).collect();
+    let harmonicity = mean(audible.iter().map(|f| f.harmonicity));
+    let flatness = mean(audible.iter().map(|f| f.flatness));
+    let flux = mean(audible.iter().map(|f| f.flux));
+
+    if flatness > NOISE_FLATNESS && harmonicity < NOISE_HARMONICITY {
+        return RegionClass::Noise;
+    }
+
+    // Syllabic energy modulation: speech dips between syllables, music mostly sustains
+    let rms_std = mean(frames.iter().map(|f| (f.rms - rms_mean).powi(2))).sqrt();
+    let modulation = rms_std / rms_mean;
+    let gaps: Vec<&FrameFeatures> = frames.iter().filter(|f| f.rms < 0.5 * rms_mean).collect();
+    let low_energy_ratio = gaps.len() as f32 / frames.len() as f32;
+
+    if modulation > SPEECH_MODULATION || low_energy_ratio > SPEECH_LOW_ENERGY_RATIO {
+        // A music bed keeps sounding (harmonically) in the gaps between syllables
+        let gap_rms = mean(gaps.iter().map(|f| f.rms));
+        let gap_harmonicity = mean(gaps.iter().map(|f| f.harmonicity));
+        return if gap_rms >= silence_threshold && gap_harmonicity > MUSIC_HARMONICITY {
+            RegionClass::SpeechOverMusic
+        } else {
+            RegionClass::Speech
+        };
+    }
+
+    if harmonicity > MUSIC_HARMONICITY || (flux > MUSIC_FLUX && flatness < NOISE_FLATNESS) {
+        RegionClass::Music
+    } else {
+        RegionClass::Noise
+    }
+}
+
+/// Classify 16 kHz mono samples into regions
+pub(crate) fn classify_samples(samples: &[f32], options: &ClassifyOptions) -> Vec<ClassifiedRegion> {
+    let duration = samples.len() as f64 / SAMPLE_RATE as f64;
+    let frames = analyze_frames(samples);
+    if frames.is_empty() {
+        return Vec::new();
+    }
+
+    let energies: Vec<f32> = frames.iter().map(|f| f.rms).collect();
+    let floor = calibrate_thresholds(&energies).noise_floor;
+    let silence_threshold = (floor * 2.0).clamp(from_db(SILENCE_MIN_DB), from_db(SILENCE_MAX_DB));
+    log::debug!("Classifier silence threshold: {:.1} dBFS", to_db(silence_threshold));
+
+    let mut labels: Vec<(usize, RegionClass)> = Vec::new();
+    let mut pos = 0;
+    loop {
+        let end = (pos + WINDOW_FRAMES).min(frames.len());
+        labels.push(((pos + end) / 2, classify_window(&frames[pos..end], silence_threshold)));
+        if end == frames.len() {
+            break;
+        }
+        pos += WINDOW_HOP_FRAMES;
+    }
+
+    // 3-window majority vote removes single-window flips
+    let smoothed: Vec<RegionClass> = (0..labels.len())
+        .map(|i| {
+            let cur = labels[i].1;
+            match (i.checked_sub(1).map(|p| labels[p].1), labels.get(i + 1).map(|l| l.1)) {
+                (Some(prev), Some(next)) if prev == next && prev != cur => prev,
+                _ => cur,
+            }
+        })
+        .collect();
+
+    // Each window owns the span between the midpoints to its neighbours
+    let frame_time = |frame: usize| (frame * HOP + FRAME / 2) as f64 / SAMPLE_RATE as f64;
+    let mut regions: Vec<ClassifiedRegion> = Vec::new();
+    for (i, &class) in smoothed.iter().enumerate() {
+        let start = if i == 0 { 0.0 } else { (frame_time(labels[i - 1].0) + frame_time(labels[i].0)) / 2.0 };
+        let end = match labels.get(i + 1) {
+            Some(next) => (frame_time(labels[i].0) + frame_time(next.0)) / 2.0,
+            None => duration,
+        };
+        push_region(&mut regions, ClassifiedRegion { start, end, class });
+    }
+
+    absorb_short_regions(regions, options.min_region_duration)
+}
+
+fn push_region(regions: &mut Vec<ClassifiedRegion>, region: ClassifiedRegion) {
+    match regions.last_mut() {
+        Some(last) if last.class == region.class => last.end = region.end,
+        _ => regions.push(region),
+    }
+}
+
+/// Merge regions shorter than `min_duration` into the preceding region
+/// (or the following one, for a short first region).
+fn absorb_short_regions(regions: Vec<ClassifiedRegion>, min_duration: f64) -> Vec<ClassifiedRegion> {
+    let mut out: Vec<ClassifiedRegion> = Vec::with_capacity(regions.len());
+    for region in regions {
+        match out.last_mut() {
+            Some(last) if region.end - region.start < min_duration => last.end = region.end,
+            Some(last) if last.end - last.start < min_duration && out.len() == 1 => {
+                let start = last.start;
+                *last = ClassifiedRegion { start, ..region };
+            }
+            _ => push_region(&mut out, region),
+        }
+    }
+
+    // Absorbing can leave equal neighbours; join them
+    let mut merged: Vec<ClassifiedRegion> = Vec::with_capacity(out.len());
+    for region in out {
+        push_region(&mut merged, region);
+    }
+    merged
+}
+
+// ── Tauri Commands ──
+
+/// Label regions of an audio file as speech, music, speech over music, noise or silence
+#[tauri::command]
+pub async fn classify_audio(
+    path: String,
+    options: Option<ClassifyOptions>,
+) -> Result<ClassificationResult, String> {
+    let opts = options.unwrap_or_default();
+
+    tokio::task::spawn_blocking(move || {
+        let t0 = Instant::now();
+        let samples = super::transcribe::load_audio_16khz_pub(Path::new(&path))?;
+        let regions = classify_samples(&samples, &opts);
+
+        log::info!("Classification: {} regions in {}ms", regions.len(), t0.elapsed().as_millis());
+
+        Ok(ClassificationResult {
+            regions,
+            duration: samples.len() as f64 / SAMPLE_RATE as f64,
+            processing_time_ms: t0.elapsed().as_millis() as u64,
+        })
+    })
+    .await
+    .map_err(|e| format!("Classification task failed: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+
+    /// Deterministic white noise in [-1, 1]
+    fn noise(n: usize, seed: u32) -> Vec<f32> {
+        let mut state = seed;
+        (0..n)
+            .map(|_| {
+                state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+                (state >> 8) as f32 / (1u32 << 23) as f32 - 1.0
+            })
+            .collect()
+    }
+
+    /// Harmonic tone (fundamental + 3 overtones)
+    fn tone(n: usize, freq: f32, amp: f32) -> Vec<f32> {
+        (0..n)
+            .map(|i| {
+                let t = i as f32 / SAMPLE_RATE as f32;
+                (1..=4).map(|h| (2.0 * PI * freq * h as f32 * t).sin() / h as f32).sum::<f32>() * amp / 2.0
+            })
+            .collect()
+    }
+
+    /// Syllable-like bursts: 150ms voiced, 100ms pause
+    fn syllables(n: usize, amp: f32) -> Vec<f32> {
+        tone(n, 140.0, amp)
+            .into_iter()
+            .enumerate()
+            .map(|(i, s)| if (i % 4000) < 2400 { s } else { 0.0 })
+            .collect()
+    }
+
+    fn class_at(regions: &[ClassifiedRegion], t: f64) -> RegionClass {
+        regions.iter().find(|r| t >= r.start && t < r.end).map(|r| r.class).unwrap()
+    }
+
+    #[test]
+    fn test_classifies_each_kind() {
+        let secs = 4 * SAMPLE_RATE;
+        let mut samples = vec![0.0f32; secs];
+        samples.extend(syllables(secs, 0.5));
+        samples.extend(tone(secs, 220.0, 0.3));
+        samples.extend(noise(secs, 7).iter().map(|s| s * 0.2));
+        let bed = tone(secs, 330.0, 0.05);
+        samples.extend(syllables(secs, 0.5).iter().zip(&bed).map(|(s, b)| s + b));
+
+        let regions = classify_samples(&samples, &ClassifyOptions::default());
+        assert_eq!(class_at(&regions, 2.0), RegionClass::Silence);
+        assert_eq!(class_at(&regions, 6.0), RegionClass::Speech);
+        assert_eq!(class_at(&regions, 10.0), RegionClass::Music);
+        assert_eq!(class_at(&regions, 14.0), RegionClass::Noise);
+        assert_eq!(class_at(&regions, 18.0), RegionClass::SpeechOverMusic);
+
+        // Regions tile the file
+        assert_eq!(regions[0].start, 0.0);
+        assert!((regions.last().unwrap().end - 20.0).abs() < 1e-9);
+        assert!(regions.windows(2).all(|w| w[0].end == w[1].start && w[0].class != w[1].class));
+    }
+
+    #[test]
+    fn test_absorb_short_regions() {
+        let r = |start: f64, end: f64, class| ClassifiedRegion { start, end, class };
+        let regions = vec![
+            r(0.0, 0.5, RegionClass::Noise),
+            r(0.5, 3.0, RegionClass::Speech),
+            r(3.0, 3.4, RegionClass::Music),
+            r(3.4, 6.0, RegionClass::Speech),
+        ];
+        let out = absorb_short_regions(regions, 1.0);
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].class, RegionClass::Speech);
+        assert_eq!((out[0].start, out[0].end), (0.0, 6.0));
+    }
+}
@@ -33,6 +33,7 @@ pub async fn clean_audio(
     end_time: Option<f64>,
     options: CleaningOptions,
     silence_segments: Option<Vec<SilenceSegmentInput>>,
+    bypass_segments: Option<Vec<SilenceSegmentInput>>,
 ) -> Result<CleanResult, String> {
     let source = Path::new(&source_path);
     let output = Path::new(&output_path);
@@ -179,6 +180,18 @@ pub async fn clean_audio(
         mono_samples.push(mono);
     }
 
+    // Keep the untouched signal for bypassed regions (e.g. music from classify_audio)
+    let bypass: Vec<(usize, usize)> = bypass_segments
+        .unwrap_or_default()
+        .iter()
+        .map(|seg| {
+            let to_frame = |t: f64| (((t - start_time.unwrap_or(0.0)).max(0.0) * sample_rate as f64) as usize).min(mono_len);
+            (to_frame(seg.start), to_frame(seg.end))
+        })
+        .filter(|(s, e)| s < e)
+        .collect();
+    let original = if bypass.is_empty() { None } else { Some(mono_samples.clone()) };
+
     // Run the cleaning pipeline
     audio_clean::process_audio(
         &mut mono_samples,
@@ -187,6 +200,10 @@ pub async fn clean_audio(
         silence_segs.as_deref(),
     )?;
 
+    if let Some(original) = original {
+        restore_bypassed(&mut mono_samples, &original, &bypass, sample_rate);
+    }
+
     // Expand mono back to original channel count
     let mut output_samples: Vec<f32> = Vec::with_capacity(region_samples.len());
     for sample in mono_samples {
@@ -225,6 +242,29 @@ pub async fn clean_audio(
     })
 }
 
+/// Crossfade length when switching between cleaned and bypassed audio
+const BYPASS_FADE_SECS: f64 = 0.01;
+
+/// Put the original signal back inside `ranges` (frame indices), crossfading at the edges
+fn restore_bypassed(cleaned: &mut [f32], original: &[f32], ranges: &[(usize, usize)], sample_rate: u32) {
+    let fade = ((BYPASS_FADE_SECS * sample_rate as f64) as usize).max(1);
+    for &(start, end) in ranges {
+        let lo = start.saturating_sub(fade);
+        let hi = (end + fade).min(cleaned.len());
+        for i in lo..hi {
+            // Weight of the original: ramps 0 -> 1 before `start` and 1 -> 0 after `end`
+            let w = if i < start {
+                1.0 - (start - i) as f32 / fade as f32
+            } else if i >= end {
+                1.0 - (i - end + 1) as f32 / fade as f32
+            } else {
+                1.0
+            };
+            cleaned[i] = original[i] * w + cleaned[i] * (1.0 - w);
+        }
+    }
+}
+
 /// Input format for silence segments from frontend
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -333,6 +373,18 @@ pub async fn get_temp_audio_path() -> Result<String, String> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_restore_bypassed_keeps_original_inside_range() {
+        let original = vec![1.0f32; 4000];
+        let mut cleaned = vec![0.0f32; 4000];
+        restore_bypassed(&mut cleaned, &original, &[(1000, 2000)], 16000);
+        assert_eq!(cleaned[500], 0.0);
+        assert_eq!(cleaned[1500], 1.0);
+        assert!(cleaned[995] > 0.0 && cleaned[995] < 1.0);
+        assert!(cleaned[2005] > 0.0 && cleaned[2005] < 1.0);
+        assert_eq!(cleaned[3000], 0.0);
+    }
+
     #[tokio::test]
     async fn test_get_temp_audio_path() {
         let path = get_temp_audio_path().await.unwrap();
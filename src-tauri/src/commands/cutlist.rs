@@ -0,0 +1,170 @@
+//! Cut-list EDLs: turn source-time keep ranges into timeline clips for `export_edl`
+
+use serde::{Deserialize, Serialize};
+
+use super::export::ExportEDLTrack;
+
+/// A range of the source file (seconds) that survives the edit
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeepRange {
+    pub start: f64,
+    pub end: f64,
+}
+
+/// Sort keep ranges, drop empty ones and merge ranges that touch or overlap.
+pub fn normalize_ranges(ranges: &[KeepRange]) -> Vec<KeepRange> {
+    let mut sorted: Vec<KeepRange> = ranges
+        .iter()
+        .filter(|r| r.end > r.start)
+        .map(|r| KeepRange { start: r.start.max(0.0), end: r.end })
+        .collect();
+    sorted.sort_by(|a, b| a.start.partial_cmp(&b.start).unwrap_or(std::cmp::Ordering::Equal));
+
+    let mut merged: Vec<KeepRange> = Vec::with_capacity(sorted.len());
+    for r in sorted {
+        if let Some(last) = merged.last_mut() {
+            if r.start <= last.end {
+                last.end = last.end.max(r.end);
+                continue;
+            }
+        }
+        merged.push(r);
+    }
+    merged
+}
+
+/// Complement of `removed` within `[0, total_duration]`.
+pub fn invert_ranges(removed: &[KeepRange], total_duration: f64) -> Vec<KeepRange> {
+    let mut keep = Vec::new();
+    let mut cursor = 0.0;
+    for r in normalize_ranges(removed) {
+        if r.start > cursor {
+            keep.push(KeepRange { start: cursor, end: r.start.min(total_duration) });
+        }
+        cursor = cursor.max(r.end);
+    }
+    if cursor < total_duration {
+        keep.push(KeepRange { start: cursor, end: total_duration });
+    }
+    keep
+}
+
+/// Lay keep ranges end-to-end on the timeline as EDL clips into `source_path`.
+///
+/// Ranges are used in the given order (sort them first for a plain cut list;
+/// text-based edits may reorder them). At every join the outgoing clip runs
+/// `crossfade / 2` past its range and the incoming clip starts `crossfade / 2`
+/// early; both fade linearly over that overlap, centred on the join, so the
+/// timeline length is exactly the sum of the kept ranges.
+pub fn ranges_to_clips(
+    source_path: &str,
+    ranges: &[KeepRange],
+    crossfade: f64,
+    source_duration: f64,
+) -> Vec<ExportEDLTrack> {
+    let half = crossfade.max(0.0) / 2.0;
+    let ranges: Vec<&KeepRange> = ranges.iter().filter(|r| r.end > r.start).collect();
+    let mut clips = Vec::with_capacity(ranges.len());
+    let mut cursor = 0.0;
+
+    for (i, r) in ranges.iter().enumerate() {
+        let is_first = i == 0;
+        let is_last = i + 1 == ranges.len();
+
+        let pre = if is_first { 0.0 } else { half.min(r.start) };
+        let post = if is_last { 0.0 } else { half.min((source_duration - r.end).max(0.0)) };
+        let length = r.end - r.start;
+
+        clips.push(ExportEDLTrack {
+            source_path: source_path.to_string(),
+            track_start: cursor - pre,
+            duration: length + pre + post,
+            volume: 1.0,
+            file_offset: r.start - pre,
+            volume_envelope: None,
+            fade_in: if pre > 0.0 { Some(pre * 2.0) } else { None },
+            fade_out: if post > 0.0 { Some(post * 2.0) } else { None },
+            source_channel: None,
+        });
+        cursor += length;
+    }
+
+    clips
+}
+
+/// Timeline length of a cut list (end of the last clip).
+pub fn clips_duration(clips: &[ExportEDLTrack]) -> f64 {
+    clips.iter().map(|c| c.track_start + c.duration).fold(0.0, f64::max)
+}
+
+/// Map a source-file time to its timeline position in a cut list.
+/// Returns `None` when the time falls in removed material.
+pub fn source_to_timeline(clips: &[ExportEDLTrack], source_path: &str, t: f64) -> Option<f64> {
+    clips
+        .iter()
+        .filter(|c| c.source_path == source_path)
+        .find(|c| t >= c.file_offset && t < c.file_offset + c.duration)
+        .map(|c| c.track_start + (t - c.file_offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn r(start: f64, end: f64) -> KeepRange {
+        KeepRange { start, end }
+    }
+
+    #[test]
+    fn test_normalize_merges_and_sorts() {
+        let merged = normalize_ranges(&[r(5.0, 6.0), r(0.0, 1.0), r(0.5, 2.0), r(3.0, 3.0)]);
+        assert_eq!(merged, vec![r(0.0, 2.0), r(5.0, 6.0)]);
+    }
+
+    #[test]
+    fn test_invert_ranges() {
+        let keep = invert_ranges(&[r(1.0, 2.0), r(4.0, 5.0)], 6.0);
+        assert_eq!(keep, vec![r(0.0, 1.0), r(2.0, 4.0), r(5.0, 6.0)]);
+        assert_eq!(invert_ranges(&[], 3.0), vec![r(0.0, 3.0)]);
+    }
+
+    #[test]
+    fn test_ranges_to_clips_ignores_trailing_empty_ranges() {
+        let clips = ranges_to_clips("a.wav", &[r(0.0, 2.0), r(3.0, 4.0), r(5.0, 5.0)], 0.02, 8.0);
+        assert_eq!(clips.len(), 2);
+        // The last real clip ends at its range, without running into removed material
+        assert!((clips[1].file_offset + clips[1].duration - 4.0).abs() < 1e-9);
+        assert_eq!(clips[1].fade_out, None);
+    }
+
+    #[test]
+    fn test_ranges_to_clips_crossfades_joins() {
+        let clips = ranges_to_clips("a.wav", &[r(0.0, 2.0), r(3.0, 4.0), r(6.0, 8.0)], 0.02, 8.0);
+        assert_eq!(clips.len(), 3);
+
+        // First clip: no lead-in, extends 10ms into the removed gap
+        assert_eq!(clips[0].track_start, 0.0);
+        assert!((clips[0].duration - 2.01).abs() < 1e-9);
+        assert!(clips[0].fade_in.is_none());
+        assert_eq!(clips[0].fade_out, Some(0.02));
+
+        // Second clip starts 10ms early on the timeline and in the source
+        assert!((clips[1].track_start - 1.99).abs() < 1e-9);
+        assert!((clips[1].file_offset - 2.99).abs() < 1e-9);
+        assert_eq!(clips[1].fade_in, Some(0.02));
+
+        // Last clip: no tail, timeline is the sum of kept lengths
+        assert!(clips[2].fade_out.is_none());
+        assert!((clips_duration(&clips) - 5.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_source_to_timeline() {
+        let clips = ranges_to_clips("a.wav", &[r(0.0, 2.0), r(3.0, 4.0)], 0.0, 4.0);
+        assert_eq!(source_to_timeline(&clips, "a.wav", 1.0), Some(1.0));
+        assert_eq!(source_to_timeline(&clips, "a.wav", 3.5), Some(2.5));
+        assert_eq!(source_to_timeline(&clips, "a.wav", 2.5), None);
+        assert_eq!(source_to_timeline(&clips, "b.wav", 1.0), None);
+    }
+}
@@ -0,0 +1,511 @@
+//! Speaker diarization ("who spoke when") using a local ONNX speaker-embedding model
+//!
+//! Speech is cut into overlapping 1.5s windows, each window is embedded with the
+//! model (80-bin log-mel fbank input, WeSpeaker / 3D-Speaker style), and the
+//! embeddings are clustered by cosine similarity into speakers.
+
+use ort::session::Session;
+use realfft::RealFftPlanner;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tauri::Manager;
+
+use super::metadata::TranscribedWord;
+use super::vad::calibrate_thresholds;
+use crate::services::path_service;
+
+const SAMPLE_RATE: usize = 16000;
+const FBANK_BINS: usize = 80;
+/// 25ms analysis window / 10ms hop (Kaldi fbank defaults)
+const FBANK_FRAME: usize = 400;
+const FBANK_HOP: usize = 160;
+const FBANK_FFT: usize = 512;
+
+/// Embedding window length and hop in seconds
+const WINDOW_SECS: f64 = 1.5;
+const WINDOW_HOP_SECS: f64 = 0.75;
+/// Minimum fraction of speech frames for a window to be embedded
+const MIN_SPEECH_FRACTION: f64 = 0.5;
+/// Default cosine similarity below which clusters are not merged
+const DEFAULT_MERGE_THRESHOLD: f32 = 0.5;
+
+/// Accepted model filenames under `<models-dir>/diarization/`
+const EMBEDDING_MODEL_FILES: &[&str] = &[
+    "speaker_embedding.onnx",
+    "wespeaker_resnet34.onnx",
+    "wespeaker_en_voxceleb_resnet34.onnx",
+    "campplus.onnx",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpeakerSegment {
+    pub start: f64,
+    pub end: f64,
+    pub speaker: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiarizationOptions {
+    /// Exact number of speakers, if known (otherwise estimated from the threshold)
+    #[serde(default)]
+    pub num_speakers: Option<usize>,
+    /// Cosine similarity needed to merge two clusters (0.0 - 1.0)
+    #[serde(default)]
+    pub merge_threshold: Option<f32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiarizationResult {
+    pub segments: Vec<SpeakerSegment>,
+    pub speakers: Vec<String>,
+    pub model_name: String,
+    pub processing_time_ms: u64,
+}
+
+// ── Model path resolution ──
+
+fn find_embedding_model(custom_path: Option<&str>, resource_dir: Option<&Path>) -> Result<PathBuf, String> {
+    for (kind, models_dir) in path_service::model_search_dirs(custom_path, resource_dir) {
+        for file in EMBEDDING_MODEL_FILES {
+            let path = models_dir.join("diarization").join(file);
+            if path.exists() {
+                log::info!("Found speaker embedding model at {}: {:?}", kind, path);
+                return Ok(path);
+            }
+        }
+    }
+
+    Err(format!(
+        "Speaker embedding model not found. Place one of {:?} in a 'diarization' subdirectory of the models folder.",
+        EMBEDDING_MODEL_FILES
+    ))
+}
+
+// ── Features ──
+
+fn hz_to_mel(hz: f32) -> f32 {
+    1127.0 * (1.0 + hz / 700.0).ln()
+}
+
+/// Triangular mel filterbank over `FBANK_FFT / 2 + 1` bins (20 Hz to Nyquist)
+fn mel_filterbank() -> Vec<Vec<f32>> {
+    let n_bins = FBANK_FFT / 2 + 1;
+    let mel_lo = hz_to_mel(20.0);
+    let mel_hi = hz_to_mel(SAMPLE_RATE as f32 / 2.0);
+    let mel_step = (mel_hi - mel_lo) / (FBANK_BINS + 1) as f32;
+    let bin_mel: Vec<f32> = (0..n_bins)
+        .map(|b| hz_to_mel(b as f32 * SAMPLE_RATE as f32 / FBANK_FFT as f32))
+        .collect();
+
+    (0..FBANK_BINS)
+        .map(|m| {
+            let left = mel_lo + m as f32 * mel_step;
+            let center = left + mel_step;
+            let right = center + mel_step;
+            bin_mel
+                .iter()
+                .map(|&mel| {
+                    if mel > left && mel <= center {
+                        (mel - left) / (center - left)
+                    } else if mel > center && mel < right {
+                        (right - mel) / (right - center)
+                    } else {
+                        0.0
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Kaldi-style log-mel fbank with per-utterance mean normalisation.
+/// Returns row-major `[frames x FBANK_BINS]`.
+fn compute_fbank(samples: &[f32], filters: &[Vec<f32>]) -> (Vec<f32>, usize) {
+    if samples.len() < FBANK_FRAME {
+        return (Vec::new(), 0);
+    }
+    let n_frames = (samples.len() - FBANK_FRAME) / FBANK_HOP + 1;
+
+    let mut planner = RealFftPlanner::<f32>::new();
+    let fft = planner.plan_fft_forward(FBANK_FFT);
+    let mut input = fft.make_input_vec();
+    let mut spectrum = fft.make_output_vec();
+
+    // Povey window (Kaldi default): Hann raised to 0.85
+    let window: Vec<f32> = (0..FBANK_FRAME)
+        .map(|i| {
+            let hann = 0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / (FBANK_FRAME - 1) as f32).cos();
+            hann.powf(0.85)
+        })
+        .collect();
+
+    let mut feats = vec![0.0f32; n_frames * FBANK_BINS];
+    for f in 0..n_frames {
+        let frame = &samples[f * FBANK_HOP..f * FBANK_HOP + FBANK_FRAME];
+
+        // Remove DC and scale to int16 range (models are trained on Kaldi features)
+        let mean = frame.iter().sum::<f32>() / FBANK_FRAME as f32;
+        let scaled: Vec<f32> = frame.iter().map(|s| (s - mean) * 32768.0).collect();
+
+        // Pre-emphasis (0.97) + window, zero-padded to the FFT size
+        input.iter_mut().for_each(|x| *x = 0.0);
+        for (i, &x) in scaled.iter().enumerate() {
+            let prev = if i > 0 { scaled[i - 1] } else { x };
+            input[i] = (x - 0.97 * prev) * window[i];
+        }
+
+        if fft.process(&mut input, &mut spectrum).is_err() {
+            continue;
+        }
+        let power: Vec<f32> = spectrum.iter().map(|c| c.norm_sqr()).collect();
+
+        for (m, filter) in filters.iter().enumerate() {
+            let energy: f32 = filter.iter().zip(&power).map(|(w, p)| w * p).sum();
+            feats[f * FBANK_BINS + m] = energy.max(f32::EPSILON).ln();
+        }
+    }
+
+    // Cepstral mean normalisation per bin
+    for m in 0..FBANK_BINS {
+        let mean = (0..n_frames).map(|f| feats[f * FBANK_BINS + m]).sum::<f32>() / n_frames as f32;
+        for f in 0..n_frames {
+            feats[f * FBANK_BINS + m] -= mean;
+        }
+    }
+
+    (feats, n_frames)
+}
+
+// ── Embedding ──
+
+fn embed_window(session: &mut Session, feats: Vec<f32>, n_frames: usize) -> Result<Vec<f32>, String> {
+    let input_name = session
+        .inputs()
+        .first()
+        .map(|i| i.name().to_string())
+        .ok_or("Embedding model has no inputs")?;
+    let output_name = session
+        .outputs()
+        .first()
+        .map(|o| o.name().to_string())
+        .ok_or("Embedding model has no outputs")?;
+
+    let arr = ndarray::Array3::from_shape_vec((1, n_frames, FBANK_BINS), feats)
+        .map_err(|e| format!("Fbank shape error: {}", e))?
+        .into_dyn();
+    let ort_inputs: Vec<(std::borrow::Cow<'_, str>, ort::value::DynValue)> = vec![(
+        input_name.into(),
+        ort::value::Value::from_array(arr)
+            .map_err(|e| format!("Fbank tensor: {}", e))?
+            .into_dyn(),
+    )];
+
+    let outputs = session
+        .run(ort_inputs)
+        .map_err(|e| format!("Embedding inference failed: {}", e))?;
+    let emb = outputs
+        .get(&*output_name)
+        .ok_or_else(|| format!("Embedding output '{}' not found", output_name))?
+        .try_extract_array::<f32>()
+        .map_err(|e| format!("Failed to extract embedding: {}", e))?;
+
+    let mut v: Vec<f32> = emb.iter().copied().collect();
+    let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt().max(1e-9);
+    v.iter_mut().for_each(|x| *x /= norm);
+    Ok(v)
+}
+
+fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let na = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let nb = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    dot / (na * nb).max(1e-9)
+}
+
+/// Cosine similarity above which windows are pre-grouped before clustering
+const PREGROUP_THRESHOLD: f32 = 0.8;
+/// Pre-group windows when there are more than this many (keeps clustering cubic in speakers, not windows)
+const PREGROUP_MIN_WINDOWS: usize = 300;
+
+/// Agglomerative clustering (centroid linkage).
+/// Merges the most similar pair until `num_speakers` clusters remain, or
+/// (when unknown) until no pair is more similar than `threshold`.
+/// Returns a cluster label per embedding, numbered by first appearance.
+pub(crate) fn cluster_embeddings(embeddings: &[Vec<f32>], num_speakers: Option<usize>, threshold: f32) -> Vec<usize> {
+    // Long files: greedily pre-group near-identical windows so the pairwise pass stays small
+    let mut clusters: Vec<(Vec<usize>, Vec<f32>)> = Vec::new();
+    for (i, e) in embeddings.iter().enumerate() {
+        let existing = if embeddings.len() > PREGROUP_MIN_WINDOWS {
+            clusters.iter_mut().find(|(_, c)| cosine(c, e) >= PREGROUP_THRESHOLD)
+        } else {
+            None
+        };
+        match existing {
+            Some((members, centroid)) => {
+                let n = members.len() as f32;
+                for (c, x) in centroid.iter_mut().zip(e) {
+                    *c = (*c * n + x) / (n + 1.0);
+                }
+                members.push(i);
+            }
+            None => clusters.push((vec![i], e.clone())),
+        }
+    }
+
+    let k = clusters.len();
+    let mut sim = vec![vec![f32::NEG_INFINITY; k]; k];
+    for i in 0..k {
+        for j in i + 1..k {
+            sim[i][j] = cosine(&clusters[i].1, &clusters[j].1);
+        }
+    }
+    let mut alive = vec![true; k];
+    let mut remaining = k;
+    let target = num_speakers.unwrap_or(1).max(1);
+
+    while remaining > target {
+        let mut best = (0, 0, f32::NEG_INFINITY);
+        for i in (0..k).filter(|&i| alive[i]) {
+            for j in (i + 1..k).filter(|&j| alive[j]) {
+                if sim[i][j] > best.2 {
+                    best = (i, j, sim[i][j]);
+                }
+            }
+        }
+        if num_speakers.is_none() && best.2 < threshold {
+            break;
+        }
+
+        // Merge j into i and refresh i's similarities
+        let (i, j, _) = best;
+        let (members_j, centroid_j) = std::mem::take(&mut clusters[j]);
+        let (members_i, centroid_i) = &mut clusters[i];
+        let (ni, nj) = (members_i.len() as f32, members_j.len() as f32);
+        for (c, x) in centroid_i.iter_mut().zip(&centroid_j) {
+            *c = (*c * ni + x * nj) / (ni + nj);
+        }
+        members_i.extend(members_j);
+        alive[j] = false;
+        remaining -= 1;
+
+        for x in (0..k).filter(|&x| alive[x] && x != i) {
+            let s = cosine(&clusters[i].1, &clusters[x].1);
+            if x < i { sim[x][i] = s; } else { sim[i][x] = s; }
+        }
+    }
+
+    // Number clusters by first appearance so speaker_1 is whoever talks first
+    let mut order: Vec<usize> = (0..k).filter(|&i| alive[i]).collect();
+    order.sort_by_key(|&i| clusters[i].0.iter().copied().min().unwrap_or(usize::MAX));
+    let mut labels = vec![0; embeddings.len()];
+    for (label, &c) in order.iter().enumerate() {
+        for &m in &clusters[c].0 {
+            labels[m] = label;
+        }
+    }
+    labels
+}
+
+fn speaker_label(index: usize) -> String {
+    format!("speaker_{}", index + 1)
+}
+
+/// Assign each word the speaker whose segments overlap it most.
+/// Words in gaps take the nearest segment's speaker.
+pub(crate) fn assign_speakers_to_words(words: &mut [TranscribedWord], segments: &[SpeakerSegment]) {
+    if segments.is_empty() {
+        return;
+    }
+    for word in words.iter_mut() {
+        let mut best: Option<(&SpeakerSegment, f64)> = None;
+        for seg in segments {
+            let overlap = word.end.min(seg.end) - word.start.max(seg.start);
+            let score = if overlap > 0.0 {
+                overlap
+            } else {
+                // Negative distance so any overlap beats any gap
+                -(seg.start - word.end).max(word.start - seg.end)
+            };
+            if best.map(|(_, s)| score > s).unwrap_or(true) {
+                best = Some((seg, score));
+            }
+        }
+        word.speaker = best.map(|(seg, _)| seg.speaker.clone());
+    }
+}
+
+fn diarize_samples(
+    session: &mut Session,
+    samples: &[f32],
+    options: &DiarizationOptions,
+) -> Result<Vec<SpeakerSegment>, String> {
+    // Speech mask from 10ms frame energies (same calibration as VAD auto mode)
+    let energies: Vec<f32> = samples
+        .chunks(FBANK_HOP)
+        .map(|c| (c.iter().map(|s| s * s).sum::<f32>() / c.len() as f32).sqrt())
+        .collect();
+    let thresholds = calibrate_thresholds(&energies);
+    let speech: Vec<bool> = energies.iter().map(|&e| e > thresholds.offset).collect();
+
+    let filters = mel_filterbank();
+    let window = (WINDOW_SECS * SAMPLE_RATE as f64) as usize;
+    let hop = (WINDOW_HOP_SECS * SAMPLE_RATE as f64) as usize;
+
+    let mut windows: Vec<(usize, usize)> = Vec::new();
+    let mut embeddings: Vec<Vec<f32>> = Vec::new();
+    let mut pos = 0;
+    while pos < samples.len() {
+        let end = (pos + window).min(samples.len());
+        if end - pos < window / 2 {
+            break;
+        }
+        let frames = &speech[pos / FBANK_HOP..(end / FBANK_HOP).min(speech.len())];
+        let speech_frac = frames.iter().filter(|s| **s).count() as f64 / frames.len().max(1) as f64;
+        if speech_frac >= MIN_SPEECH_FRACTION {
+            let (feats, n_frames) = compute_fbank(&samples[pos..end], &filters);
+            if n_frames > 0 {
+                embeddings.push(embed_window(session, feats, n_frames)?);
+                windows.push((pos, end));
+            }
+        }
+        pos += hop;
+    }
+
+    if embeddings.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let labels = cluster_embeddings(
+        &embeddings,
+        options.num_speakers,
+        options.merge_threshold.unwrap_or(DEFAULT_MERGE_THRESHOLD),
+    );
+
+    // Each window owns the middle of its span (hop-sized), so overlapping windows don't fight
+    let mut segments: Vec<SpeakerSegment> = Vec::new();
+    for (&(start, end), &label) in windows.iter().zip(&labels) {
+        let center = (start + end) / 2;
+        let own_start = center.saturating_sub(hop / 2).max(start);
+        let own_end = (center + hop / 2).min(end);
+        let seg_start = own_start as f64 / SAMPLE_RATE as f64;
+        let seg_end = own_end as f64 / SAMPLE_RATE as f64;
+        let speaker = speaker_label(label);
+
+        match segments.last_mut() {
+            Some(last) if last.speaker == speaker && seg_start - last.end < WINDOW_HOP_SECS => {
+                last.end = seg_end;
+            }
+            _ => segments.push(SpeakerSegment { start: seg_start, end: seg_end, speaker }),
+        }
+    }
+
+    Ok(segments)
+}
+
+// ── Tauri Commands ──
+
+/// Detect who spoke when in an audio file
+#[tauri::command]
+pub async fn diarize_audio(
+    path: String,
+    options: Option<DiarizationOptions>,
+    models_path: Option<String>,
+    app: tauri::AppHandle,
+) -> Result<DiarizationResult, String> {
+    let resource_dir = app.path().resource_dir().ok();
+    let model_path = find_embedding_model(models_path.as_deref(), resource_dir.as_deref())?;
+    let opts = options.unwrap_or_default();
+
+    tokio::task::spawn_blocking(move || {
+        let t0 = Instant::now();
+        let samples = super::transcribe::load_audio_16khz_pub(Path::new(&path))?;
+        let mut session = super::moonshine::init_session(&model_path)?;
+        let segments = diarize_samples(&mut session, &samples, &opts)?;
+
+        let mut speakers: Vec<String> = segments.iter().map(|s| s.speaker.clone()).collect();
+        speakers.sort();
+        speakers.dedup();
+
+        log::info!(
+            "Diarization: {} segments, {} speakers in {}ms",
+            segments.len(), speakers.len(), t0.elapsed().as_millis()
+        );
+
+        Ok(DiarizationResult {
+            segments,
+            speakers,
+            model_name: model_path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            processing_time_ms: t0.elapsed().as_millis() as u64,
+        })
+    })
+    .await
+    .map_err(|e| format!("Diarization task failed: {}", e))?
+}
+
+/// Merge diarization segments into transcript words (sets `speaker` on each word)
+#[tauri::command]
+pub async fn assign_speakers(
+    mut words: Vec<TranscribedWord>,
+    segments: Vec<SpeakerSegment>,
+) -> Result<Vec<TranscribedWord>, String> {
+    assign_speakers_to_words(&mut words, &segments);
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::metadata::test_transcribed_word;
+
+    #[test]
+    fn test_cluster_two_speakers_by_threshold() {
+        let a = vec![1.0, 0.0, 0.0];
+        let a2 = vec![0.95, 0.1, 0.0];
+        let b = vec![0.0, 1.0, 0.0];
+        let b2 = vec![0.1, 0.9, 0.1];
+        let labels = cluster_embeddings(&[a, b, a2, b2], None, 0.5);
+        assert_eq!(labels, vec![0, 1, 0, 1]);
+    }
+
+    #[test]
+    fn test_cluster_respects_num_speakers() {
+        let embs = vec![vec![1.0, 0.0], vec![0.0, 1.0], vec![0.7, 0.7]];
+        let labels = cluster_embeddings(&embs, Some(1), 0.99);
+        assert_eq!(labels, vec![0, 0, 0]);
+    }
+
+    #[test]
+    fn test_assign_speakers_by_overlap_and_nearest() {
+        let mut words = vec![
+            test_transcribed_word("a", "a", 0.0, 0.5),
+            test_transcribed_word("b", "b", 1.9, 2.4),
+            test_transcribed_word("c", "c", 5.0, 5.2),
+        ];
+        let segments = vec![
+            SpeakerSegment { start: 0.0, end: 2.0, speaker: "speaker_1".into() },
+            SpeakerSegment { start: 2.0, end: 4.0, speaker: "speaker_2".into() },
+        ];
+        assign_speakers_to_words(&mut words, &segments);
+        assert_eq!(words[0].speaker.as_deref(), Some("speaker_1"));
+        assert_eq!(words[1].speaker.as_deref(), Some("speaker_2"));
+        assert_eq!(words[2].speaker.as_deref(), Some("speaker_2"));
+    }
+
+    #[test]
+    fn test_fbank_shape() {
+        let samples: Vec<f32> = (0..SAMPLE_RATE)
+            .map(|i| (i as f32 * 440.0 * 2.0 * std::f32::consts::PI / SAMPLE_RATE as f32).sin() * 0.5)
+            .collect();
+        let (feats, frames) = compute_fbank(&samples, &mel_filterbank());
+        assert_eq!(frames, (SAMPLE_RATE - FBANK_FRAME) / FBANK_HOP + 1);
+        assert_eq!(feats.len(), frames * FBANK_BINS);
+        assert!(feats.iter().all(|f| f.is_finite()));
+    }
+}
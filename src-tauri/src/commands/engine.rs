@@ -0,0 +1,310 @@
+//! Common interface over the local speech-to-text engines (Whisper, Moonshine).
+//!
+//! Each engine implements `TranscriptionEngine` in its own module and is listed in
+//! `ENGINES`; the `transcribe`, `list_transcription_engines` and
+//! `check_transcription_model` commands dispatch by engine ID.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use tauri::{AppHandle, Manager};
+
+use super::models;
+use super::moonshine::MoonshineEngine;
+use super::transcribe::{self, TranscriptionResult, WhisperEngine, TASK_TRANSCRIBE, TASK_TRANSLATE};
+
+/// Registered engines; add new local engines here
+static ENGINES: &[&dyn TranscriptionEngine] = &[&WhisperEngine, &MoonshineEngine];
+
+/// A model an engine can use, and whether it's installed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EngineModel {
+    pub name: String,
+    pub available: bool,
+    pub path: Option<String>,
+    /// Download size (0 when unknown)
+    pub size_mb: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub download_url: Option<String>,
+}
+
+/// What an engine supports, so the UI can hide options it would ignore
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EngineCapabilities {
+    /// Language codes it can transcribe (empty = any Whisper language)
+    pub languages: Vec<String>,
+    pub language_detection: bool,
+    pub translation: bool,
+    /// Initial prompt / hotwords
+    pub vocabulary: bool,
+    /// Beam size, best-of and temperature
+    pub decoding_options: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EngineInfo {
+    pub id: String,
+    pub name: String,
+    pub capabilities: EngineCapabilities,
+    pub models: Vec<EngineModel>,
+}
+
+/// Options for `transcribe`; engines ignore the ones their capabilities don't list
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TranscribeOptions {
+    /// Model name from `list_transcription_engines` (default: the engine's default
+    /// model set with `set_default_model`, else the first installed one)
+    pub model: Option<String>,
+    /// Language code, or "auto" to detect it
+    pub language: Option<String>,
+    /// "transcribe" or "translate"
+    pub task: Option<String>,
+    pub beam_size: Option<i32>,
+    pub best_of: Option<i32>,
+    pub temperature: Option<f32>,
+    pub initial_prompt: Option<String>,
+    pub hotwords: Option<Vec<String>>,
+}
+
+/// Where engines look for models besides their defaults
+#[derive(Debug, Clone, Default)]
+pub struct ModelLocations {
+    /// User-configured models directory
+    pub models_path: Option<String>,
+    /// Bundled resources (release builds)
+    pub resource_dir: Option<PathBuf>,
+}
+
+impl ModelLocations {
+    pub fn new(app: &AppHandle, models_path: Option<String>) -> Self {
+        Self { models_path, resource_dir: app.path().resource_dir().ok() }
+    }
+
+    pub fn custom_path(&self) -> Option<&str> {
+        self.models_path.as_deref().filter(|p| !p.is_empty())
+    }
+
+    pub fn resource_dir(&self) -> Option<&Path> {
+        self.resource_dir.as_deref()
+    }
+}
+
+/// One transcription request. Word times are absolute even for a sub-range.
+#[derive(Debug, Clone)]
+pub struct TranscriptionJob {
+    pub path: String,
+    pub start_time: Option<f64>,
+    pub end_time: Option<f64>,
+    pub options: TranscribeOptions,
+    pub locations: ModelLocations,
+}
+
+/// Reports a running session's progress as `transcription-progress` events
+pub struct ProgressReporter {
+    app: AppHandle,
+    session_id: String,
+}
+
+impl ProgressReporter {
+    pub fn new(app: &AppHandle, session_id: &str) -> Self {
+        Self { app: app.clone(), session_id: session_id.to_string() }
+    }
+
+    /// `stage` is "loading", "detecting_language" or "decoding"; `progress` is 0.0 - 1.0
+    pub fn report(&self, stage: &str, progress: f32) {
+        transcribe::emit_progress(&self.app, &self.session_id, stage, progress);
+    }
+
+    pub fn app(&self) -> &AppHandle {
+        &self.app
+    }
+
+    pub fn session_id(&self) -> &str {
+        &self.session_id
+    }
+}
+
+pub trait TranscriptionEngine: Send + Sync {
+    /// Stable ID used by the frontend and in `TranscriptionMetrics::engine`
+    fn id(&self) -> &'static str;
+
+    fn name(&self) -> &'static str;
+
+    fn capabilities(&self) -> EngineCapabilities;
+
+    fn list_models(&self, locations: &ModelLocations) -> Vec<EngineModel>;
+
+    /// Path of the named model, or of the default one when `model` is None
+    fn check_model(&self, model: Option<&str>, locations: &ModelLocations) -> Result<PathBuf, String>;
+
+    /// Blocking; runs on a worker thread. Should poll `cancel` between chunks and
+    /// return `CANCELLED_MESSAGE` when it's set.
+    fn transcribe(
+        &self,
+        job: &TranscriptionJob,
+        progress: &ProgressReporter,
+        cancel: &Arc<AtomicBool>,
+    ) -> Result<TranscriptionResult, String>;
+
+    /// Copy a model from `source` (file or folder) into `models_dir` after checking it
+    /// really is a model for this engine. `name` overrides the name read from the model.
+    fn install_model(&self, source: &Path, name: Option<&str>, models_dir: &Path) -> Result<PathBuf, String> {
+        let _ = (source, name, models_dir);
+        Err(format!("{} models can't be imported", self.name()))
+    }
+
+    /// Where model `name` is installed in `models_dir`, if it is there
+    fn installed_model(&self, name: &str, models_dir: &Path) -> Option<PathBuf> {
+        let _ = (name, models_dir);
+        None
+    }
+
+    /// Drop anything cached for the model at `path` (before it's deleted)
+    fn unload_model(&self, path: &Path) {
+        let _ = path;
+    }
+
+    /// Reject options the engine can't honour instead of silently ignoring them
+    fn validate(&self, options: &TranscribeOptions) -> Result<(), String> {
+        let caps = self.capabilities();
+        match options.task.as_deref() {
+            None | Some(TASK_TRANSCRIBE) => {}
+            Some(TASK_TRANSLATE) if caps.translation => {}
+            Some(TASK_TRANSLATE) => return Err(format!("{} cannot translate", self.name())),
+            Some(task) => return Err(format!("Unknown task '{}': expected \"transcribe\" or \"translate\"", task)),
+        }
+        if let Some(language) = options.language.as_deref().map(str::trim).filter(|l| !l.is_empty()) {
+            if language.eq_ignore_ascii_case("auto") {
+                if !caps.language_detection && caps.languages.len() != 1 {
+                    return Err(format!("{} cannot detect the language", self.name()));
+                }
+            } else if !caps.languages.is_empty() && !caps.languages.iter().any(|l| l.eq_ignore_ascii_case(language)) {
+                return Err(format!(
+                    "{} does not support language '{}' (supported: {})",
+                    self.name(), language, caps.languages.join(", ")
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn engines() -> &'static [&'static dyn TranscriptionEngine] {
+    ENGINES
+}
+
+pub(crate) fn find_engine(id: &str) -> Result<&'static dyn TranscriptionEngine, String> {
+    ENGINES.iter().copied().find(|e| e.id() == id).ok_or_else(|| {
+        let known: Vec<&str> = ENGINES.iter().map(|e| e.id()).collect();
+        format!("Unknown transcription engine '{}' (available: {})", id, known.join(", "))
+    })
+}
+
+/// Transcribe with any engine.
+///
+/// Runs as a session like `transcribe_audio`: pass `session_id` to receive
+/// `transcription-progress` events and to cancel it with `cancel_transcription`.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn transcribe(
+    app: AppHandle,
+    path: String,
+    engine: String,
+    options: Option<TranscribeOptions>,
+    models_path: Option<String>,
+    session_id: Option<String>,
+    start_time: Option<f64>,
+    end_time: Option<f64>,
+) -> Result<TranscriptionResult, String> {
+    let engine = find_engine(&engine)?;
+    let mut options = options.unwrap_or_default();
+    engine.validate(&options)?;
+    if options.model.is_none() {
+        options.model = models::default_model(engine.id());
+    }
+
+    log::info!("Transcribing {} with {} (model: {:?})", path, engine.id(), options.model);
+    let job = TranscriptionJob {
+        path,
+        start_time,
+        end_time,
+        options,
+        locations: ModelLocations::new(&app, models_path),
+    };
+    transcribe::run_transcription_session(&app, session_id, move |session_id, cancel, app| {
+        engine.transcribe(&job, &ProgressReporter::new(app, session_id), cancel)
+    })
+    .await
+}
+
+/// All engines with their capabilities and models
+#[tauri::command]
+pub async fn list_transcription_engines(
+    app: AppHandle,
+    models_path: Option<String>,
+) -> Result<Vec<EngineInfo>, String> {
+    let locations = ModelLocations::new(&app, models_path);
+    Ok(ENGINES
+        .iter()
+        .map(|engine| EngineInfo {
+            id: engine.id().to_string(),
+            name: engine.name().to_string(),
+            capabilities: engine.capabilities(),
+            models: engine.list_models(&locations),
+        })
+        .collect())
+}
+
+/// Path of an engine's model (the default one when `model` is omitted)
+#[tauri::command]
+pub async fn check_transcription_model(
+    app: AppHandle,
+    engine: String,
+    model: Option<String>,
+    models_path: Option<String>,
+) -> Result<String, String> {
+    let engine = find_engine(&engine)?;
+    let locations = ModelLocations::new(&app, models_path);
+    let model = model.or_else(|| models::default_model(engine.id()));
+    engine
+        .check_model(model.as_deref(), &locations)
+        .map(|p| p.to_string_lossy().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_engine_registry() {
+        let ids: Vec<&str> = ENGINES.iter().map(|e| e.id()).collect();
+        assert_eq!(ids, vec!["whisper", "moonshine"]);
+        assert_eq!(find_engine("moonshine").unwrap().id(), "moonshine");
+        let err = find_engine("vosk").err().unwrap();
+        assert!(err.contains("whisper, moonshine"), "{}", err);
+    }
+
+    #[test]
+    fn test_validate_against_capabilities() {
+        let options = |task: Option<&str>, language: Option<&str>| TranscribeOptions {
+            task: task.map(str::to_string),
+            language: language.map(str::to_string),
+            ..Default::default()
+        };
+        let whisper = find_engine("whisper").unwrap();
+        let moonshine = find_engine("moonshine").unwrap();
+
+        assert!(whisper.validate(&options(Some("translate"), Some("auto"))).is_ok());
+        assert!(whisper.validate(&options(Some("summarize"), None)).is_err());
+        assert!(moonshine.validate(&options(None, Some("EN"))).is_ok());
+        // English-only: "auto" is trivially English
+        assert!(moonshine.validate(&options(None, Some("auto"))).is_ok());
+        assert!(moonshine.validate(&options(Some("translate"), None)).is_err());
+        assert!(moonshine.validate(&options(None, Some("de"))).unwrap_err().contains("'de'"));
+    }
+}
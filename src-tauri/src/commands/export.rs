@@ -1,7 +1,9 @@
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::BufWriter;
 use std::path::{Path, PathBuf};
 use std::num::{NonZeroU32, NonZeroU8};
+use std::sync::Arc;
 use symphonia::core::audio::SampleBuffer;
 use symphonia::core::codecs::DecoderOptions;
 use symphonia::core::formats::FormatOptions;
@@ -10,11 +12,12 @@ use symphonia::core::meta::MetadataOptions;
 use symphonia::core::probe::Hint;
 use hound::{WavSpec, WavWriter};
 use vorbis_rs::{VorbisBitrateManagementStrategy, VorbisEncoderBuilder};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use tauri::Emitter;
 
 use crate::audio_util::Rf64Writer;
 use super::playback::{PcmData, AutomationPoint, load_wav_mmap, load_compressed};
+use super::redact::{self, ExportRedaction};
 
 #[tauri::command]
 pub async fn export_audio_region(
@@ -210,9 +213,12 @@ pub struct ExportEDL {
     pub ogg_quality: Option<f32>,  // 0.0–1.0 for OGG Vorbis quality
     pub start_time: f64,
     pub end_time: f64,
+    /// Ranges or transcript words to bleep/silence in the output
+    #[serde(default)]
+    pub redaction: Option<ExportRedaction>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExportEDLTrack {
     pub source_path: String,
     pub track_start: f64,  // timeline offset in seconds
@@ -248,7 +254,8 @@ struct EdlSource {
     fade_out: f64,
     /// Source channel to extract (None = all channels interleaved)
     source_channel: Option<u8>,
-    pcm: PcmData,
+    /// Shared between clips cut from the same source file (loaded once per export)
+    pcm: Arc<PcmData>,
     sample_rate: u32,
     channels: u16,
 }
@@ -260,21 +267,38 @@ pub async fn export_edl(edl: ExportEDL, app: tauri::AppHandle) -> Result<String,
     }).await.map_err(|e| format!("Export task failed: {}", e))?
 }
 
-fn export_edl_inner(edl: &ExportEDL, app: &tauri::AppHandle) -> Result<String, String> {
+pub(crate) fn export_edl_inner(edl: &ExportEDL, app: &tauri::AppHandle) -> Result<String, String> {
     log::info!(
         "EDL export: {} tracks, format={}, {}Hz {}ch, {:.1}s-{:.1}s",
         edl.tracks.len(), edl.format, edl.sample_rate, edl.channels,
         edl.start_time, edl.end_time,
     );
 
-    // Load all track sources
+    // Resolve word redactions to timeline ranges once, before any mixing
+    let resolved;
+    let edl = match &edl.redaction {
+        Some(redaction) => {
+            let redaction = redact::resolve_redaction(redaction, &edl.tracks)?;
+            log::info!("  Redacting {} ranges", redaction.ranges.len());
+            resolved = ExportEDL { redaction: Some(redaction), ..edl.clone() };
+            &resolved
+        }
+        None => edl,
+    };
+
+    // Load all track sources (cut lists reference the same file many times — load each once)
+    let mut loaded: HashMap<String, (Arc<PcmData>, u32, u16)> = HashMap::new();
     let mut sources: Vec<EdlSource> = Vec::new();
     for track in &edl.tracks {
-        let (pcm, sample_rate, channels) = match load_wav_mmap(&track.source_path) {
-            Ok(result) => result,
-            Err(_) => load_compressed(&track.source_path)?,
-        };
-        log::info!("  Loaded source: {}Hz {}ch {} samples", sample_rate, channels, pcm.len());
+        if !loaded.contains_key(&track.source_path) {
+            let (pcm, sample_rate, channels) = match load_wav_mmap(&track.source_path) {
+                Ok(result) => result,
+                Err(_) => load_compressed(&track.source_path)?,
+            };
+            log::info!("  Loaded source: {}Hz {}ch {} samples", sample_rate, channels, pcm.len());
+            loaded.insert(track.source_path.clone(), (Arc::new(pcm), sample_rate, channels));
+        }
+        let (pcm, sample_rate, channels) = loaded[&track.source_path].clone();
         sources.push(EdlSource {
             track_start: track.track_start,
             duration: track.duration,
@@ -412,6 +436,10 @@ fn export_edl_wav(
                 sources, frames_written, chunk_size,
                 edl.start_time, output_rate, output_channels, &mut mix_buf,
             );
+            redact::apply_redactions(
+                &mut mix_buf, edl.redaction.as_ref(), frames_written,
+                edl.start_time, output_rate, output_channels,
+            );
 
             for &sample in &mix_buf {
                 writer.write_sample(sample)
@@ -447,6 +475,10 @@ fn export_edl_wav(
                 sources, frames_written, chunk_size,
                 edl.start_time, output_rate, output_channels, &mut mix_buf,
             );
+            redact::apply_redactions(
+                &mut mix_buf, edl.redaction.as_ref(), frames_written,
+                edl.start_time, output_rate, output_channels,
+            );
 
             for &sample in &mix_buf {
                 writer.write_sample(sample)
@@ -619,6 +651,10 @@ fn export_edl_ogg(
             sources, frames_written, chunk_size,
             edl.start_time, output_rate_f64, output_channels, &mut mix_buf,
         );
+        redact::apply_redactions(
+            &mut mix_buf, edl.redaction.as_ref(), frames_written,
+            edl.start_time, output_rate_f64, output_channels,
+        );
 
         // Deinterleave to planar f32 for Vorbis
         let mut planar: Vec<Vec<f32>> = vec![Vec::with_capacity(chunk_size); output_channels];
@@ -867,6 +903,10 @@ fn export_edl_wav_no_progress(
                 sources, frames_written, chunk_size,
                 edl.start_time, output_rate, output_channels, &mut mix_buf,
             );
+            redact::apply_redactions(
+                &mut mix_buf, edl.redaction.as_ref(), frames_written,
+                edl.start_time, output_rate, output_channels,
+            );
             for &sample in &mix_buf {
                 writer.write_sample(sample)
                     .map_err(|e| format!("Write error: {}", e))?;
@@ -892,6 +932,10 @@ fn export_edl_wav_no_progress(
                 sources, frames_written, chunk_size,
                 edl.start_time, output_rate, output_channels, &mut mix_buf,
             );
+            redact::apply_redactions(
+                &mut mix_buf, edl.redaction.as_ref(), frames_written,
+                edl.start_time, output_rate, output_channels,
+            );
             for &sample in &mix_buf {
                 writer.write_sample(sample)
                     .map_err(|e| format!("Write error: {}", e))?;
@@ -969,6 +1013,10 @@ fn export_edl_ogg_no_progress(
             sources, frames_written, chunk_size,
             edl.start_time, output_rate_f64, output_channels, &mut mix_buf,
         );
+        redact::apply_redactions(
+            &mut mix_buf, edl.redaction.as_ref(), frames_written,
+            edl.start_time, output_rate_f64, output_channels,
+        );
 
         let mut planar: Vec<Vec<f32>> = vec![Vec::with_capacity(chunk_size); output_channels];
         for i in 0..chunk_size {
@@ -1212,7 +1260,10 @@ mod tests {
             file_offset: 0.0,
             volume: 1.0,
             volume_envelope: None,
-            pcm,
+            fade_in: 0.0,
+            fade_out: 0.0,
+            source_channel: None,
+            pcm: Arc::new(pcm),
             sample_rate,
             channels,
         }
@@ -1285,6 +1336,9 @@ mod tests {
                 volume: 1.0,
                 file_offset: 0.0,
                 volume_envelope: None,
+                fade_in: None,
+                fade_out: None,
+                source_channel: None,
             }],
             output_path: output_path.to_str().unwrap().to_string(),
             format: "wav".to_string(),
@@ -1294,6 +1348,7 @@ mod tests {
             ogg_quality: None,
             start_time: 0.0,
             end_time: 1.0,
+            redaction: None,
         };
 
         export_edl_wav_no_progress(&edl, &[source], total_frames).unwrap();
@@ -1345,6 +1400,9 @@ mod tests {
                 volume: 1.0,
                 file_offset: 0.0,
                 volume_envelope: None,
+                fade_in: None,
+                fade_out: None,
+                source_channel: None,
             }],
             output_path: output_orig.to_str().unwrap().to_string(),
             format: "mp3".to_string(),
@@ -1354,6 +1412,7 @@ mod tests {
             ogg_quality: None,
             start_time: 0.0,
             end_time: 2.0,
+            redaction: None,
         };
         let total_orig = (2.0 * SAMPLE_RATE as f64) as usize;
         export_edl_mp3_no_progress(&edl_orig, &[source_orig], total_orig).unwrap();
@@ -1368,6 +1427,9 @@ mod tests {
                 volume: 1.0,
                 file_offset: 0.0,
                 volume_envelope: None,
+                fade_in: None,
+                fade_out: None,
+                source_channel: None,
             }],
             output_path: output_edited.to_str().unwrap().to_string(),
             format: "mp3".to_string(),
@@ -1377,6 +1439,7 @@ mod tests {
             ogg_quality: None,
             start_time: 0.0,
             end_time: 1.0,
+            redaction: None,
         };
         let total_edited = (1.0 * SAMPLE_RATE as f64) as usize;
         export_edl_mp3_no_progress(&edl_edited, &[source_edited], total_edited).unwrap();
@@ -1419,6 +1482,9 @@ mod tests {
                 volume: 1.0,
                 file_offset: 0.0,
                 volume_envelope: None,
+                fade_in: None,
+                fade_out: None,
+                source_channel: None,
             }],
             output_path: output_orig.to_str().unwrap().to_string(),
             format: "ogg".to_string(),
@@ -1428,6 +1494,7 @@ mod tests {
             ogg_quality: Some(0.4),
             start_time: 0.0,
             end_time: 2.0,
+            redaction: None,
         };
         let total_orig = (2.0 * SAMPLE_RATE as f64) as usize;
         export_edl_ogg_no_progress(&edl_orig, &[source_orig], total_orig).unwrap();
@@ -1442,6 +1509,9 @@ mod tests {
                 volume: 1.0,
                 file_offset: 0.0,
                 volume_envelope: None,
+                fade_in: None,
+                fade_out: None,
+                source_channel: None,
             }],
             output_path: output_edited.to_str().unwrap().to_string(),
             format: "ogg".to_string(),
@@ -1451,6 +1521,7 @@ mod tests {
             ogg_quality: Some(0.4),
             start_time: 0.0,
             end_time: 1.0,
+            redaction: None,
         };
         let total_edited = (1.0 * SAMPLE_RATE as f64) as usize;
         export_edl_ogg_no_progress(&edl_edited, &[source_edited], total_edited).unwrap();
@@ -1501,6 +1572,9 @@ mod tests {
                 volume: 1.0,
                 file_offset: 0.0,
                 volume_envelope: None,
+                fade_in: None,
+                fade_out: None,
+                source_channel: None,
             }],
             output_path: temp_wav_orig.to_str().unwrap().to_string(),
             format: "wav".to_string(),
@@ -1510,6 +1584,7 @@ mod tests {
             ogg_quality: None,
             start_time: 0.0,
             end_time: 2.0,
+            redaction: None,
         };
         let total_orig = (2.0 * SAMPLE_RATE as f64) as usize;
         export_edl_wav_no_progress(&edl_orig, &[source_orig], total_orig).unwrap();
@@ -1526,6 +1601,9 @@ mod tests {
                 volume: 1.0,
                 file_offset: 0.0,
                 volume_envelope: None,
+                fade_in: None,
+                fade_out: None,
+                source_channel: None,
             }],
             output_path: temp_wav_edited.to_str().unwrap().to_string(),
             format: "wav".to_string(),
@@ -1535,6 +1613,7 @@ mod tests {
             ogg_quality: None,
             start_time: 0.0,
             end_time: 1.0,
+            redaction: None,
         };
         let total_edited = (1.0 * SAMPLE_RATE as f64) as usize;
         export_edl_wav_no_progress(&edl_edited, &[source_edited], total_edited).unwrap();
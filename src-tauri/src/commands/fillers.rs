@@ -0,0 +1,207 @@
+//! Filler-word detection ("um", "uh", "you know") and cut lists that remove them
+
+use serde::{Deserialize, Serialize};
+
+use super::cutlist::{self, KeepRange};
+use super::export::ExportEDLTrack;
+use super::transcribe::Word;
+
+/// Default crossfade at filler cuts in seconds
+const DEFAULT_FILLER_CROSSFADE: f64 = 0.015;
+
+const DEFAULT_FILLERS: &[&str] = &[
+    "um", "umm", "uh", "uhm", "erm", "er", "ah", "hmm", "mm", "mhm", "you know", "i mean",
+];
+
+fn default_fillers() -> Vec<String> {
+    DEFAULT_FILLERS.iter().map(|s| s.to_string()).collect()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FillerOptions {
+    /// Filler words and phrases to match (case and punctuation are ignored)
+    #[serde(default = "default_fillers")]
+    pub fillers: Vec<String>,
+    /// Skip matches recognised with less confidence than this (0.0 = keep all)
+    #[serde(default)]
+    pub min_confidence: f64,
+}
+
+impl Default for FillerOptions {
+    fn default() -> Self {
+        Self {
+            fillers: default_fillers(),
+            min_confidence: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FillerMatch {
+    /// IDs of the transcript words making up the filler (one per token of the phrase)
+    pub word_ids: Vec<String>,
+    pub text: String,
+    pub start: f64,
+    pub end: f64,
+    /// Lowest confidence among the matched words
+    pub confidence: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FillerCutList {
+    pub clips: Vec<ExportEDLTrack>,
+    pub original_duration: f64,
+    pub output_duration: f64,
+    pub removed_count: usize,
+}
+
+/// Lowercase and strip punctuation so "Um," matches "um" (apostrophes are kept).
+fn normalize_token(text: &str) -> String {
+    text.chars()
+        .filter(|c| c.is_alphanumeric() || *c == '\'')
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+/// Find filler words and phrases in a word-level transcript.
+/// Longer phrases win over shorter ones starting at the same word.
+pub(crate) fn find_fillers(words: &[Word], options: &FillerOptions) -> Vec<FillerMatch> {
+    let mut phrases: Vec<Vec<String>> = options
+        .fillers
+        .iter()
+        .map(|f| f.split_whitespace().map(normalize_token).filter(|t| !t.is_empty()).collect::<Vec<_>>())
+        .filter(|p| !p.is_empty())
+        .collect();
+    phrases.sort_by_key(|p| std::cmp::Reverse(p.len()));
+
+    let tokens: Vec<String> = words.iter().map(|w| normalize_token(&w.text)).collect();
+    let mut matches = Vec::new();
+    let mut i = 0;
+
+    while i < words.len() {
+        let hit = phrases.iter().find(|phrase| {
+            i + phrase.len() <= tokens.len()
+                && phrase.iter().zip(&tokens[i..]).all(|(p, t)| p == t)
+        });
+
+        match hit {
+            Some(phrase) => {
+                let span = &words[i..i + phrase.len()];
+                let confidence = span.iter().map(|w| w.confidence).fold(f64::INFINITY, f64::min);
+                if confidence >= options.min_confidence {
+                    matches.push(FillerMatch {
+                        word_ids: span.iter().map(|w| w.id.clone()).collect(),
+                        text: span.iter().map(|w| w.text.as_str()).collect::<Vec<_>>().join(" "),
+                        start: span[0].start,
+                        end: span[span.len() - 1].end,
+                        confidence,
+                    });
+                }
+                i += phrase.len();
+            }
+            None => i += 1,
+        }
+    }
+
+    matches
+}
+
+/// Detect filler words in a transcript and return their time ranges
+#[tauri::command]
+pub async fn detect_filler_words(
+    words: Vec<Word>,
+    options: Option<FillerOptions>,
+) -> Result<Vec<FillerMatch>, String> {
+    let opts = options.unwrap_or_default();
+    let matches = find_fillers(&words, &opts);
+    log::info!("Filler detection: {} matches in {} words", matches.len(), words.len());
+    Ok(matches)
+}
+
+/// Build a cut-list EDL into `source_path` that removes the given filler matches
+#[tauri::command]
+pub async fn build_filler_cut_list(
+    source_path: String,
+    matches: Vec<FillerMatch>,
+    crossfade: Option<f64>,
+) -> Result<FillerCutList, String> {
+    let meta = super::audio::get_audio_metadata(source_path.clone()).await?;
+    let total_duration = if meta.duration > 0.0 {
+        meta.duration
+    } else {
+        matches.iter().map(|m| m.end).fold(0.0, f64::max)
+    };
+
+    let removed: Vec<KeepRange> = matches
+        .iter()
+        .map(|m| KeepRange { start: m.start, end: m.end })
+        .collect();
+    let keep = cutlist::invert_ranges(&removed, total_duration);
+    let clips = cutlist::ranges_to_clips(
+        &source_path,
+        &keep,
+        crossfade.unwrap_or(DEFAULT_FILLER_CROSSFADE),
+        total_duration,
+    );
+    let output_duration = cutlist::clips_duration(&clips);
+
+    log::info!(
+        "Filler cut list: removed {} fillers, {:.1}s -> {:.1}s",
+        matches.len(), total_duration, output_duration
+    );
+
+    Ok(FillerCutList {
+        clips,
+        original_duration: total_duration,
+        output_duration,
+        removed_count: matches.len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::transcribe::test_word;
+
+    #[test]
+    fn test_finds_single_and_multi_word_fillers() {
+        let words = vec![
+            test_word("1", "So,", 0.0, 0.3),
+            test_word("2", "Um,", 0.4, 0.7),
+            test_word("3", "you", 0.8, 1.1),
+            test_word("4", "know", 1.1, 1.4),
+            test_word("5", "it", 1.5, 1.8),
+            test_word("6", "works.", 1.8, 2.1),
+        ];
+        let matches = find_fillers(&words, &FillerOptions::default());
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].word_ids, vec!["2"]);
+        assert_eq!(matches[1].word_ids, vec!["3", "4"]);
+        assert!((matches[1].start - 0.8).abs() < 1e-9);
+        assert!((matches[1].end - 1.4).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_min_confidence_skips_uncertain_matches() {
+        let words = vec![
+            Word { confidence: 0.3, ..test_word("1", "uh", 0.0, 0.3) },
+            Word { confidence: 0.95, ..test_word("2", "um", 0.5, 0.8) },
+        ];
+        let opts = FillerOptions { min_confidence: 0.5, ..Default::default() };
+        let matches = find_fillers(&words, &opts);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].word_ids, vec!["2"]);
+    }
+
+    #[test]
+    fn test_custom_list_does_not_match_substrings() {
+        let words = vec![test_word("1", "umbrella", 0.0, 0.3), test_word("2", "like", 0.5, 0.8)];
+        let opts = FillerOptions { fillers: vec!["um".into(), "like".into()], min_confidence: 0.0 };
+        let matches = find_fillers(&words, &opts);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].text, "like");
+    }
+}
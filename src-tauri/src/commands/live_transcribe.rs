@@ -0,0 +1,349 @@
+//! Live transcription while recording.
+//!
+//! A tap on a recording session's ring buffer gets a copy of every batch the WAV
+//! writer drains. The batches are downmixed and resampled to 16 kHz and fed to
+//! Moonshine in short windows; each window's words are emitted as
+//! `live-transcription-words` with times relative to the start of the recording.
+//! When the recording stops the writer closes the tap, so only the last window is
+//! left to decode and the full transcript follows right away as
+//! `live-transcription-complete` (and from `stop_live_transcription`).
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, State};
+
+use super::chunking::{Resampler16k, SampleSource, WindowConfig};
+use super::engine::ModelLocations;
+use super::moonshine;
+use super::recording::{RecordingManager, TapChunk};
+use super::transcribe::Word;
+
+/// Short windows keep words coming every few seconds; Moonshine decodes a window
+/// in a fraction of its length on CPU, so the worker keeps up with the recording
+const LIVE_WINDOW: WindowConfig = WindowConfig { window: 6.0, overlap: 1.0, search: 2.0 };
+/// How often a waiting worker checks for cancellation
+const TAP_POLL: Duration = Duration::from_millis(100);
+/// Tap chunks queued for a worker that falls behind (about 10s at the writer's 5ms
+/// drain interval). Chunks beyond that are dropped and later filled with silence.
+const TAP_CAPACITY: usize = 2048;
+
+/// Recording session used by `start_recording`
+const DEFAULT_RECORDING_SESSION: &str = "default";
+
+/// Words decoded from the latest window (final; later windows don't revise them)
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LiveWordsEvent {
+    session_id: String,
+    words: Vec<Word>,
+}
+
+/// Transcript of a live session
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LiveTranscript {
+    pub text: String,
+    pub words: Vec<Word>,
+    /// Recording time replaced with silence because the worker fell behind and tap
+    /// audio was dropped. When non-zero the transcript has holes; re-transcribe the
+    /// recorded file for a complete one.
+    pub dropped_secs: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LiveCompleteEvent {
+    session_id: String,
+    text: String,
+    words: Vec<Word>,
+    dropped_secs: f64,
+    error: Option<String>,
+}
+
+/// A running live transcription, keyed by recording session ID
+struct LiveSession {
+    cancel: Arc<AtomicBool>,
+    handle: JoinHandle<Result<LiveTranscript, String>>,
+}
+
+pub struct LiveTranscriptionState {
+    sessions: Mutex<HashMap<String, LiveSession>>,
+}
+
+impl LiveTranscriptionState {
+    pub fn new() -> Self {
+        Self {
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+/// The tapped recording as a 16 kHz stream. `read` blocks until audio arrives and
+/// reports the end when the tap closes (recording stopped) or `cancel` is set.
+struct TapSource {
+    rx: Receiver<TapChunk>,
+    sample_rate: u32,
+    channels: u16,
+    resampler: Resampler16k,
+    /// Ring position of the first sample received
+    first_position: Option<usize>,
+    /// Ring position expected next; a later chunk means samples were skipped
+    next_position: Option<usize>,
+    ended: bool,
+    cancel: Arc<AtomicBool>,
+    /// Interleaved samples filled with silence so far (shared, as the source is
+    /// moved into the transcriber)
+    missing_samples: Arc<AtomicUsize>,
+}
+
+impl TapSource {
+    fn new(rx: Receiver<TapChunk>, sample_rate: u32, channels: u16, cancel: Arc<AtomicBool>) -> Self {
+        Self {
+            rx,
+            sample_rate,
+            channels,
+            resampler: Resampler16k::new(sample_rate as f64),
+            first_position: None,
+            next_position: None,
+            ended: false,
+            cancel,
+            missing_samples: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Wait for the first chunk; returns its time in the recording (seconds), or
+    /// None if the recording stopped or the session was cancelled first
+    fn wait_for_start(&mut self) -> Option<f64> {
+        while self.first_position.is_none() && !self.ended {
+            self.receive();
+        }
+        let position = self.first_position?;
+        Some(self.samples_to_secs(position))
+    }
+
+    /// Interleaved sample count (or ring position) in seconds
+    fn samples_to_secs(&self, samples: usize) -> f64 {
+        samples as f64 / self.channels.max(1) as f64 / self.sample_rate as f64
+    }
+
+    fn receive(&mut self) {
+        if self.cancel.load(Ordering::Relaxed) {
+            self.ended = true;
+            return;
+        }
+        match self.rx.recv_timeout(TAP_POLL) {
+            Ok(chunk) => {
+                if let Some(expected) = self.next_position {
+                    // Keep times right if anything was skipped
+                    let missing = chunk.position.saturating_sub(expected);
+                    if missing > 0 {
+                        log::warn!("Live transcription: {} samples missing from the tap, filling with silence", missing);
+                        self.resampler.push(&vec![0.0; missing], self.channels as usize);
+                        self.missing_samples.fetch_add(missing, Ordering::Relaxed);
+                    }
+                }
+                self.first_position.get_or_insert(chunk.position);
+                self.resampler.push(&chunk.samples, self.channels as usize);
+                self.next_position = Some(chunk.position + chunk.samples.len());
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => self.ended = true,
+        }
+    }
+}
+
+impl SampleSource for TapSource {
+    fn read(&mut self, max: usize, out: &mut Vec<f32>) -> Result<usize, String> {
+        loop {
+            let produced = self.resampler.resample(max, out);
+            if produced > 0 {
+                return Ok(produced);
+            }
+            if self.ended {
+                return Ok(self.resampler.flush(max, out));
+            }
+            self.receive();
+        }
+    }
+}
+
+/// Worker thread body: transcribe the tapped audio until the recording stops
+fn run_live_session(
+    app: &AppHandle,
+    session_id: &str,
+    variant_name: &str,
+    model_dir: &Path,
+    mut source: TapSource,
+    cancel: &AtomicBool,
+) -> Result<LiveTranscript, String> {
+    let missing_samples = source.missing_samples.clone();
+    let secs_per_sample = source.samples_to_secs(1);
+    let (text, words, duration) = match source.wait_for_start() {
+        Some(offset) => {
+            log::info!("Live transcription of '{}' starts at {:.2}s", session_id, offset);
+            moonshine::transcribe_chunked(model_dir, variant_name, source, offset, LIVE_WINDOW, cancel, |words, _| {
+                if !words.is_empty() {
+                    let _ = app.emit("live-transcription-words", LiveWordsEvent {
+                        session_id: session_id.to_string(),
+                        words: words.to_vec(),
+                    });
+                }
+            })?
+        }
+        None => (String::new(), Vec::new(), 0.0),
+    };
+
+    let dropped_secs = missing_samples.load(Ordering::Relaxed) as f64 * secs_per_sample;
+    if dropped_secs > 0.0 {
+        log::warn!("Live transcription of '{}' is missing {:.1}s of dropped audio", session_id, dropped_secs);
+    }
+    log::info!("Live transcription of '{}' complete: {} words, {:.1}s", session_id, words.len(), duration);
+    Ok(LiveTranscript { text, words, dropped_secs })
+}
+
+/// Start transcribing a recording session as it records (default: the
+/// `start_recording` session). Words arrive as `live-transcription-words` events.
+#[tauri::command]
+pub async fn start_live_transcription(
+    app: AppHandle,
+    session_id: Option<String>,
+    model: Option<String>,
+    models_path: Option<String>,
+    recording: State<'_, RecordingManager>,
+    live: State<'_, LiveTranscriptionState>,
+) -> Result<(), String> {
+    let session_id = session_id.unwrap_or_else(|| DEFAULT_RECORDING_SESSION.to_string());
+    let mut sessions = live.sessions.lock().map_err(|_| "Live transcription lock poisoned".to_string())?;
+    if sessions.get(&session_id).is_some_and(|s| !s.handle.is_finished()) {
+        return Err(format!("Live transcription already running for session '{}'", session_id));
+    }
+
+    let (variant_name, model_dir) =
+        moonshine::locate_moonshine_model(model.as_deref(), &ModelLocations::new(&app, models_path))?;
+    let (tx, rx) = mpsc::sync_channel(TAP_CAPACITY);
+    let (sample_rate, channels) = recording.attach_tap(&session_id, tx)?;
+    log::info!(
+        "Starting live transcription of '{}' ({}Hz {}ch) with moonshine {}",
+        session_id, sample_rate, channels, variant_name
+    );
+
+    let cancel = Arc::new(AtomicBool::new(false));
+    let source = TapSource::new(rx, sample_rate, channels, cancel.clone());
+    let worker_cancel = cancel.clone();
+    let worker_session = session_id.clone();
+    let handle = std::thread::Builder::new()
+        .name("live-transcribe".into())
+        .spawn(move || {
+            let result = run_live_session(&app, &worker_session, &variant_name, &model_dir, source, &worker_cancel);
+            let event = match &result {
+                Ok(r) => LiveCompleteEvent {
+                    session_id: worker_session.clone(),
+                    text: r.text.clone(),
+                    words: r.words.clone(),
+                    dropped_secs: r.dropped_secs,
+                    error: None,
+                },
+                Err(e) => LiveCompleteEvent {
+                    session_id: worker_session.clone(),
+                    text: String::new(),
+                    words: Vec::new(),
+                    dropped_secs: 0.0,
+                    error: Some(e.clone()),
+                },
+            };
+            let _ = app.emit("live-transcription-complete", event);
+            result
+        })
+        .map_err(|e| format!("Failed to spawn live transcription thread: {}", e))?;
+
+    sessions.insert(session_id, LiveSession { cancel, handle });
+    Ok(())
+}
+
+/// Finish a live transcription and return the full transcript. Call it after
+/// stopping the recording to get everything; while the recording is still running
+/// it stops transcribing at the current point. Check `dropped_secs` before treating
+/// the transcript as complete.
+#[tauri::command]
+pub async fn stop_live_transcription(
+    session_id: Option<String>,
+    recording: State<'_, RecordingManager>,
+    live: State<'_, LiveTranscriptionState>,
+) -> Result<LiveTranscript, String> {
+    let session_id = session_id.unwrap_or_else(|| DEFAULT_RECORDING_SESSION.to_string());
+    let session = live
+        .sessions
+        .lock()
+        .map_err(|_| "Live transcription lock poisoned".to_string())?
+        .remove(&session_id)
+        .ok_or_else(|| format!("No live transcription for session '{}'", session_id))?;
+
+    recording.detach_tap(&session_id);
+    tokio::task::spawn_blocking(move || session.handle.join())
+        .await
+        .map_err(|e| format!("Live transcription task failed: {}", e))?
+        .map_err(|_| "Live transcription thread panicked".to_string())?
+}
+
+/// Abandon a live transcription without waiting for its transcript
+#[tauri::command]
+pub async fn cancel_live_transcription(
+    session_id: Option<String>,
+    recording: State<'_, RecordingManager>,
+    live: State<'_, LiveTranscriptionState>,
+) -> Result<(), String> {
+    let session_id = session_id.unwrap_or_else(|| DEFAULT_RECORDING_SESSION.to_string());
+    let session = live
+        .sessions
+        .lock()
+        .map_err(|_| "Live transcription lock poisoned".to_string())?
+        .remove(&session_id);
+    if let Some(session) = session {
+        session.cancel.store(true, Ordering::SeqCst);
+        recording.detach_tap(&session_id);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tap_source_offsets_and_gaps() {
+        let (tx, rx) = mpsc::sync_channel(TAP_CAPACITY);
+        let cancel = Arc::new(AtomicBool::new(false));
+        let mut source = TapSource::new(rx, 16000, 1, cancel);
+
+        // Tap attached 2s into a 16 kHz mono recording; 0.5s then a 0.25s gap then 0.5s
+        tx.send(TapChunk { position: 32000, samples: vec![0.1; 8000] }).unwrap();
+        tx.send(TapChunk { position: 44000, samples: vec![0.2; 8000] }).unwrap();
+        drop(tx);
+
+        let offset = source.wait_for_start().unwrap();
+        assert!((offset - 2.0).abs() < 1e-3, "offset {}", offset);
+
+        let mut out = Vec::new();
+        while source.read(3000, &mut out).unwrap() > 0 {}
+        assert_eq!(out.len(), 20000);
+        assert_eq!(out[7990], 0.1);
+        assert_eq!(out[10000], 0.0);
+        assert_eq!(source.samples_to_secs(source.missing_samples.load(Ordering::Relaxed)), 0.25);
+        assert_eq!(out[19990], 0.2);
+    }
+
+    #[test]
+    fn test_tap_source_ends_on_cancel() {
+        let (_tx, rx) = mpsc::sync_channel::<TapChunk>(TAP_CAPACITY);
+        let cancel = Arc::new(AtomicBool::new(true));
+        let mut source = TapSource::new(rx, 48000, 2, cancel);
+        assert_eq!(source.wait_for_start(), None);
+        assert_eq!(source.read(100, &mut Vec::new()).unwrap(), 0);
+    }
+}
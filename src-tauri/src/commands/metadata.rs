@@ -1,8 +1,17 @@
 //! Transcription metadata persistence
 
 use serde::{Deserialize, Serialize};
-use std::fs;
-use std::path::Path;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::SystemTime;
+
+use super::diarize::SpeakerSegment;
+use crate::services::path_service;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -19,9 +28,29 @@ pub struct TranscribedWord {
     pub start: f64,
     pub end: f64,
     pub confidence: f64,
+    /// Speaker label from diarization (e.g. "speaker_1")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speaker: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// `test_word` without a speaker label, for tests
+#[cfg(test)]
+pub(crate) fn test_transcribed_word(id: &str, text: &str, start: f64, end: f64) -> TranscribedWord {
+    let w = super::transcribe::test_word(id, text, start, end);
+    TranscribedWord { id: w.id, text: w.text, start, end, confidence: w.confidence, speaker: None }
+}
+
+/// Recurring names and jargon fed to Whisper's prompt so they're spelled correctly
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptionVocabulary {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub initial_prompt: Option<String>,
+    #[serde(default)]
+    pub hotwords: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TranscriptionMetadata {
     pub audio_path: String,
@@ -33,9 +62,88 @@ pub struct TranscriptionMetadata {
     pub words: Option<Vec<TranscribedWord>>,
     pub full_text: Option<String>,
     pub language: Option<String>,
+    /// Auto-detection confidence for `language`, when the engine detected it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language_probability: Option<f32>,
+    /// English translation of `words` (Whisper "translate" task), kept alongside the source-language words
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub translated_words: Option<Vec<TranscribedWord>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub translated_text: Option<String>,
+    /// Diarization result ("who spoke when")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speakers: Option<Vec<SpeakerSegment>>,
+    /// Prompt and hotwords used for (re-)transcribing this file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vocabulary: Option<TranscriptionVocabulary>,
+}
+
+impl TranscriptionMetadata {
+    /// Seconds to shift a word (by ID): the global offset plus its own adjustment
+    pub(crate) fn word_shift(&self) -> impl Fn(&str) -> f64 + '_ {
+        let adjustments: HashMap<&str, f64> =
+            self.word_adjustments.iter().map(|a| (a.word_id.as_str(), a.offset_ms)).collect();
+        move |word_id| (self.global_offset_ms + adjustments.get(word_id).copied().unwrap_or(0.0)) / 1000.0
+    }
 }
 
-/// Get the metadata file path for an audio file
+/// Hash computed for a path, with the size and mtime it was computed at
+type HashCacheEntry = (u64, SystemTime, String);
+
+/// Hashes already computed this session, invalidated by size/mtime changes
+static HASH_CACHE: OnceLock<Mutex<HashMap<PathBuf, HashCacheEntry>>> = OnceLock::new();
+
+/// Result of looking up a transcription by audio content
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptionLookup {
+    pub audio_hash: String,
+    /// Path the transcription was saved for
+    pub stored_audio_path: String,
+    /// True when the file has been moved or renamed since then
+    pub moved: bool,
+    pub metadata: TranscriptionMetadata,
+}
+
+/// Content hash (SHA-256, hex) identifying an audio file independent of its path.
+///
+/// The whole file is hashed, so takes that only differ somewhere in the middle still
+/// get different keys; `HASH_CACHE` keeps that to once per file and session.
+pub(crate) fn content_hash(path: &Path) -> Result<String, String> {
+    let mut file = File::open(path)
+        .map_err(|e| format!("Failed to open {} for hashing: {}", path.display(), e))?;
+    let stat = file.metadata()
+        .map_err(|e| format!("Failed to stat {}: {}", path.display(), e))?;
+    let len = stat.len();
+    let mtime = stat.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+
+    let cache = HASH_CACHE.get_or_init(|| Mutex::new(HashMap::new()));
+    if let Some((cached_len, cached_mtime, hash)) = cache.lock().ok().and_then(|c| c.get(path).cloned()) {
+        if cached_len == len && cached_mtime == mtime {
+            return Ok(hash);
+        }
+    }
+
+    let mut hasher = Sha256::new();
+    hasher.update(len.to_le_bytes());
+    std::io::copy(&mut BufReader::with_capacity(1024 * 1024, &mut file), &mut hasher)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let hash = format!("{:x}", hasher.finalize());
+
+    if let Ok(mut c) = cache.lock() {
+        c.insert(path.to_path_buf(), (len, mtime, hash.clone()));
+    }
+    Ok(hash)
+}
+
+/// Central store: `{app_data_dir}/transcriptions/{hash}.json`
+fn store_dir() -> Result<PathBuf, String> {
+    let data_dir = path_service::get_user_data_dir()
+        .map_err(|e| format!("Path service error: {}", e))?;
+    Ok(data_dir.join("transcriptions"))
+}
+
+/// Sidecar mirror next to the audio file: `<stem>.transcription.json`
 fn get_metadata_path(audio_path: &str) -> std::path::PathBuf {
     let audio = Path::new(audio_path);
     let parent = audio.parent().unwrap_or(Path::new("."));
@@ -43,50 +151,309 @@ fn get_metadata_path(audio_path: &str) -> std::path::PathBuf {
     parent.join(format!("{}.transcription.json", stem))
 }
 
-/// Save transcription timing metadata
+/// Version written in the `schemaVersion` field of the on-disk envelope
+pub const METADATA_SCHEMA_VERSION: u64 = 1;
+
+/// Upgrade steps for the on-disk JSON, indexed by the version they upgrade from
+const MIGRATIONS: &[fn(Value) -> Result<Value, String>] = &[migrate_v0_to_v1];
+
+/// v0: the bare `TranscriptionMetadata` object written before versioning. Early files
+/// predate the offset/adjustment fields.
+fn migrate_v0_to_v1(value: Value) -> Result<Value, String> {
+    let Value::Object(mut metadata) = value else {
+        return Err("expected a JSON object".to_string());
+    };
+    metadata.entry("audioPath").or_insert(json!(""));
+    metadata.entry("globalOffsetMs").or_insert(json!(0.0));
+    metadata.entry("wordAdjustments").or_insert(json!([]));
+    metadata.entry("savedAt").or_insert(json!(0));
+    Ok(json!({ "schemaVersion": 1, "metadata": metadata }))
+}
+
+/// Bring stored JSON of any known version up to `METADATA_SCHEMA_VERSION` and decode it
+fn migrate_metadata(mut value: Value) -> Result<TranscriptionMetadata, String> {
+    let mut version = match value.get("schemaVersion") {
+        Some(v) => v.as_u64().ok_or("schemaVersion is not a number")?,
+        None => 0,
+    };
+    if version > METADATA_SCHEMA_VERSION {
+        return Err(format!(
+            "written by a newer version of the app (schema {}, this version reads up to {})",
+            version, METADATA_SCHEMA_VERSION
+        ));
+    }
+    while version < METADATA_SCHEMA_VERSION {
+        value = MIGRATIONS[version as usize](value)
+            .map_err(|e| format!("migration from schema {} failed: {}", version, e))?;
+        version += 1;
+    }
+
+    let metadata = value
+        .get_mut("metadata")
+        .map(Value::take)
+        .ok_or("missing metadata object")?;
+    serde_json::from_value(metadata).map_err(|e| e.to_string())
+}
+
+/// Repair word timings in place: drop words with invalid times or starting past the end
+/// of the audio, sort by start, and fix inverted, overlapping or overlong words.
+/// Returns a description of each kind of repair made.
+pub(crate) fn repair_words(words: &mut Vec<TranscribedWord>, duration: Option<f64>) -> Vec<String> {
+    let mut repairs = Vec::new();
+
+    let before = words.len();
+    words.retain(|w| {
+        w.start.is_finite() && w.end.is_finite() && w.start >= 0.0
+            && !matches!(duration, Some(d) if w.start >= d)
+    });
+    if words.len() < before {
+        repairs.push(format!("dropped {} words with invalid or out-of-range times", before - words.len()));
+    }
+
+    if words.windows(2).any(|p| p[1].start < p[0].start) {
+        words.sort_by(|a, b| a.start.partial_cmp(&b.start).unwrap_or(std::cmp::Ordering::Equal));
+        repairs.push("re-sorted words by start time".to_string());
+    }
+
+    let mut fixed = 0;
+    for i in 0..words.len() {
+        let mut end = words[i].end.max(words[i].start);
+        if let Some(next) = words.get(i + 1) {
+            end = end.min(next.start.max(words[i].start));
+        }
+        if let Some(d) = duration {
+            end = end.min(d);
+        }
+        if end != words[i].end {
+            words[i].end = end;
+            fixed += 1;
+        }
+    }
+    if fixed > 0 {
+        repairs.push(format!("fixed the end time of {} inverted, overlapping or overlong words", fixed));
+    }
+    repairs
+}
+
+/// Validate loaded metadata against the audio, repairing what can be repaired
+fn repair_metadata(metadata: &mut TranscriptionMetadata, duration: Option<f64>) {
+    let mut repairs = Vec::new();
+    if let Some(words) = metadata.words.as_mut() {
+        repairs.extend(repair_words(words, duration));
+    }
+    if let Some(words) = metadata.translated_words.as_mut() {
+        repairs.extend(repair_words(words, duration).into_iter().map(|r| format!("translation: {}", r)));
+    }
+    for repair in repairs {
+        log::warn!("Transcription metadata for {}: {}", metadata.audio_path, repair);
+    }
+}
+
+/// Move an unreadable metadata file aside so it can be inspected (and doesn't block
+/// saving a fresh transcription). Returns where it went.
+fn quarantine_metadata_file(meta_path: &Path) -> Option<PathBuf> {
+    let stamp = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let mut name = meta_path.file_name()?.to_os_string();
+    name.push(format!(".corrupt-{}", stamp));
+    let target = meta_path.with_file_name(name);
+    fs::rename(meta_path, &target).ok()?;
+    Some(target)
+}
+
+fn read_metadata_file(meta_path: &Path) -> Result<Option<TranscriptionMetadata>, String> {
+    if !meta_path.exists() {
+        return Ok(None);
+    }
+
+    let json = fs::read_to_string(meta_path)
+        .map_err(|e| format!("Failed to read metadata file: {}", e))?;
+
+    let value = match serde_json::from_str::<Value>(&json) {
+        Ok(value) => value,
+        Err(e) => {
+            let moved = quarantine_metadata_file(meta_path);
+            log::warn!("Corrupt transcription metadata {:?}: {} (moved to {:?})", meta_path, e, moved);
+            return Err(match moved {
+                Some(target) => format!(
+                    "Transcription metadata {} is unreadable ({}). It was moved to {} and the file can be transcribed again.",
+                    meta_path.display(), e, target.display()
+                ),
+                None => format!("Transcription metadata {} is unreadable ({})", meta_path.display(), e),
+            });
+        }
+    };
+    // Valid JSON that doesn't decode (e.g. written by a newer version) stays where it is
+    let mut metadata = migrate_metadata(value)
+        .map_err(|e| format!("Transcription metadata {} can't be loaded: {}", meta_path.display(), e))?;
+    repair_metadata(&mut metadata, None);
+    Ok(Some(metadata))
+}
+
+/// Write the versioned envelope via a temp file, so a crash never leaves a half-written file
+fn write_metadata_file(meta_path: &Path, metadata: &TranscriptionMetadata) -> Result<(), String> {
+    let envelope = json!({ "schemaVersion": METADATA_SCHEMA_VERSION, "metadata": metadata });
+    let json = serde_json::to_string_pretty(&envelope)
+        .map_err(|e| format!("Failed to serialize metadata: {}", e))?;
+    let mut tmp_name = meta_path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(".tmp");
+    let tmp_path = meta_path.with_file_name(tmp_name);
+    fs::write(&tmp_path, json)
+        .and_then(|_| fs::rename(&tmp_path, meta_path))
+        .map_err(|e| format!("Failed to write metadata file: {}", e))
+}
+
+/// Whether a sidecar belongs to this audio file. Sidecars are keyed by stem only, so
+/// `take.wav` and `take.mp3` share one; the stored hash (or, for old sidecars without
+/// one, the file name) tells them apart.
+fn sidecar_matches(metadata: &TranscriptionMetadata, audio_path: &str, hash: &str) -> bool {
+    match &metadata.audio_hash {
+        Some(stored) => stored == hash,
+        None => Path::new(&metadata.audio_path).file_name() == Path::new(audio_path).file_name(),
+    }
+}
+
+/// Stored metadata for an audio file: the central store by content hash, falling back
+/// to a matching sidecar (which enters the store when it's next saved). Never writes;
+/// returns the metadata as stored, i.e. with the path it was saved under.
+fn load_stored(store: &Path, audio_path: &str) -> Result<Option<TranscriptionMetadata>, String> {
+    let hash = content_hash(Path::new(audio_path))?;
+    let entry = store.join(format!("{}.json", hash));
+    // A corrupt store entry falls back to the sidecar mirror
+    let store_error = match read_metadata_file(&entry) {
+        Ok(Some(metadata)) => return Ok(Some(metadata)),
+        Ok(None) => None,
+        Err(e) => Some(e),
+    };
+
+    let sidecar = get_metadata_path(audio_path);
+    let sidecar_metadata = read_metadata_file(&sidecar).unwrap_or_else(|e| {
+        log::warn!("Ignoring sidecar {:?}: {}", sidecar, e);
+        None
+    });
+    let Some(mut metadata) = sidecar_metadata else {
+        return store_error.map_or(Ok(None), Err);
+    };
+    if !sidecar_matches(&metadata, audio_path, &hash) {
+        log::info!("Ignoring sidecar {:?}: it belongs to a different file", sidecar);
+        return store_error.map_or(Ok(None), Err);
+    }
+    metadata.audio_hash = Some(hash);
+    Ok(Some(metadata))
+}
+
+/// Write metadata to the central store (and optionally the sidecar mirror)
+fn save_stored(
+    store: &Path,
+    audio_path: &str,
+    metadata: &mut TranscriptionMetadata,
+    mirror_sidecar: bool,
+) -> Result<PathBuf, String> {
+    let hash = content_hash(Path::new(audio_path))?;
+    metadata.audio_path = audio_path.to_string();
+    metadata.audio_hash = Some(hash.clone());
+
+    fs::create_dir_all(store)
+        .map_err(|e| format!("Failed to create transcription store: {}", e))?;
+    let entry = store.join(format!("{}.json", hash));
+    write_metadata_file(&entry, metadata)?;
+    if mirror_sidecar {
+        write_metadata_file(&get_metadata_path(audio_path), metadata)?;
+    }
+    Ok(entry)
+}
+
+/// Stored metadata with `audio_path` pointing at where the file is now, validated
+/// against the audio duration when known
+fn load_current(audio_path: &str, duration: Option<f64>) -> Result<Option<TranscriptionMetadata>, String> {
+    Ok(load_stored(&store_dir()?, audio_path)?.map(|mut m| {
+        m.audio_path = audio_path.to_string();
+        if duration.is_some() {
+            repair_metadata(&mut m, duration);
+        }
+        m
+    }))
+}
+
+/// Audio duration for validating word times (None if the file can't be probed)
+async fn audio_duration(audio_path: &str) -> Option<f64> {
+    super::audio::get_audio_metadata(audio_path.to_string())
+        .await
+        .ok()
+        .map(|m| m.duration)
+        .filter(|d| *d > 0.0)
+}
+
+/// Vocabulary stored with an audio file's transcription, if any
+pub(crate) fn load_vocabulary(audio_path: &str) -> Option<TranscriptionVocabulary> {
+    load_current(audio_path, None)
+        .ok()
+        .flatten()
+        .and_then(|m| m.vocabulary)
+}
+
+/// Save transcription timing metadata (central store, plus the sidecar unless
+/// `mirror_sidecar` is false)
 #[tauri::command]
 pub async fn save_transcription_metadata(
     audio_path: String,
-    metadata: TranscriptionMetadata,
+    mut metadata: TranscriptionMetadata,
+    mirror_sidecar: Option<bool>,
 ) -> Result<(), String> {
-    let meta_path = get_metadata_path(&audio_path);
-
-    let json = serde_json::to_string_pretty(&metadata)
-        .map_err(|e| format!("Failed to serialize metadata: {}", e))?;
-
-    fs::write(&meta_path, json)
-        .map_err(|e| format!("Failed to write metadata file: {}", e))?;
+    // Keep the stored vocabulary when the caller doesn't send one
+    if metadata.vocabulary.is_none() {
+        metadata.vocabulary = load_vocabulary(&audio_path);
+    }
 
-    log::info!("Saved transcription metadata to {:?}", meta_path);
+    let entry = save_stored(&store_dir()?, &audio_path, &mut metadata, mirror_sidecar.unwrap_or(true))?;
+    log::info!("Saved transcription metadata to {:?}", entry);
     Ok(())
 }
 
-/// Load transcription timing metadata
+/// Load transcription timing metadata (found by content, so moved/renamed files keep it)
 #[tauri::command]
 pub async fn load_transcription_metadata(
     audio_path: String,
 ) -> Result<Option<TranscriptionMetadata>, String> {
-    let meta_path = get_metadata_path(&audio_path);
+    let metadata = load_current(&audio_path, audio_duration(&audio_path).await)?;
 
-    if !meta_path.exists() {
-        return Ok(None);
+    if metadata.is_some() {
+        log::info!("Loaded transcription metadata for {}", audio_path);
     }
+    Ok(metadata)
+}
 
-    let json = fs::read_to_string(&meta_path)
-        .map_err(|e| format!("Failed to read metadata file: {}", e))?;
-
-    let metadata: TranscriptionMetadata = serde_json::from_str(&json)
-        .map_err(|e| format!("Failed to parse metadata: {}", e))?;
-
-    log::info!("Loaded transcription metadata from {:?}", meta_path);
-    Ok(Some(metadata))
+/// Find an existing transcription for a file by its content, e.g. after a move or rename
+#[tauri::command]
+pub async fn lookup_transcription_by_content(
+    audio_path: String,
+) -> Result<Option<TranscriptionLookup>, String> {
+    let Some(mut metadata) = load_stored(&store_dir()?, &audio_path)? else { return Ok(None) };
+    repair_metadata(&mut metadata, audio_duration(&audio_path).await);
+    let audio_hash = metadata.audio_hash.clone().unwrap_or_default();
+    let stored_audio_path = std::mem::replace(&mut metadata.audio_path, audio_path.clone());
+    let moved = stored_audio_path != audio_path;
+    if moved {
+        log::info!("Found transcription for {} saved as {}", audio_path, stored_audio_path);
+    }
+    Ok(Some(TranscriptionLookup { audio_hash, stored_audio_path, moved, metadata }))
 }
 
-/// Delete transcription timing metadata
+/// Delete transcription timing metadata (store entry and sidecar)
 #[tauri::command]
 pub async fn delete_transcription_metadata(audio_path: String) -> Result<(), String> {
-    let meta_path = get_metadata_path(&audio_path);
+    if let Ok(hash) = content_hash(Path::new(&audio_path)) {
+        let entry = store_dir()?.join(format!("{}.json", hash));
+        if entry.exists() {
+            fs::remove_file(&entry)
+                .map_err(|e| format!("Failed to delete metadata file: {}", e))?;
+            log::info!("Deleted transcription metadata at {:?}", entry);
+        }
+    }
 
+    let meta_path = get_metadata_path(&audio_path);
     if meta_path.exists() {
         fs::remove_file(&meta_path)
             .map_err(|e| format!("Failed to delete metadata file: {}", e))?;
@@ -95,3 +462,236 @@ pub async fn delete_transcription_metadata(audio_path: String) -> Result<(), Str
 
     Ok(())
 }
+
+/// Get the custom vocabulary stored for an audio file
+#[tauri::command]
+pub async fn get_transcription_vocabulary(
+    audio_path: String,
+) -> Result<Option<TranscriptionVocabulary>, String> {
+    Ok(load_current(&audio_path, None)?.and_then(|m| m.vocabulary))
+}
+
+/// Store the custom vocabulary for an audio file (creates the metadata if needed)
+#[tauri::command]
+pub async fn set_transcription_vocabulary(
+    audio_path: String,
+    vocabulary: TranscriptionVocabulary,
+) -> Result<(), String> {
+    let store = store_dir()?;
+    let mut metadata = load_stored(&store, &audio_path)?.unwrap_or_else(|| TranscriptionMetadata {
+        audio_path: audio_path.clone(),
+        ..Default::default()
+    });
+    metadata.saved_at = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+
+    let hotwords: Vec<String> = vocabulary
+        .hotwords
+        .iter()
+        .map(|w| w.trim().to_string())
+        .filter(|w| !w.is_empty())
+        .collect();
+    let initial_prompt = vocabulary.initial_prompt.filter(|p| !p.trim().is_empty());
+    metadata.vocabulary = if hotwords.is_empty() && initial_prompt.is_none() {
+        None
+    } else {
+        Some(TranscriptionVocabulary { initial_prompt, hotwords })
+    };
+
+    let entry = save_stored(&store, &audio_path, &mut metadata, true)?;
+    log::info!("Saved transcription vocabulary to {:?}", entry);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn empty_metadata(audio_path: &str) -> TranscriptionMetadata {
+        TranscriptionMetadata {
+            audio_path: audio_path.to_string(),
+            global_offset_ms: 12.0,
+            saved_at: 1,
+            full_text: Some("hello there".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_content_hash_ignores_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("a.wav");
+        let b = dir.path().join("renamed.wav");
+        let c = dir.path().join("other.wav");
+        fs::write(&a, b"same audio bytes").unwrap();
+        fs::write(&c, b"different bytes!").unwrap();
+
+        let hash = content_hash(&a).unwrap();
+        assert_eq!(hash.len(), 64);
+        fs::rename(&a, &b).unwrap();
+        assert_eq!(content_hash(&b).unwrap(), hash);
+        assert_ne!(content_hash(&c).unwrap(), hash);
+    }
+
+    #[test]
+    fn test_content_hash_covers_whole_file() {
+        // Two long takes of silence that differ only in one byte a quarter of the way in
+        let dir = tempfile::tempdir().unwrap();
+        let mut audio = vec![0u8; 16 * 1024 * 1024];
+        let first = dir.path().join("take1.wav");
+        fs::write(&first, &audio).unwrap();
+        audio[4 * 1024 * 1024 + 100] = 1;
+        let second = dir.path().join("take2.wav");
+        fs::write(&second, &audio).unwrap();
+        assert_ne!(content_hash(&first).unwrap(), content_hash(&second).unwrap());
+    }
+
+    #[test]
+    fn test_store_survives_move() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = dir.path().join("store");
+        let old = dir.path().join("take.wav");
+        fs::write(&old, b"recording").unwrap();
+        let old_path = old.to_str().unwrap();
+
+        let mut metadata = empty_metadata(old_path);
+        save_stored(&store, old_path, &mut metadata, true).unwrap();
+        assert!(get_metadata_path(old_path).exists());
+
+        // Move the audio without its sidecar
+        fs::create_dir(dir.path().join("moved")).unwrap();
+        let new = dir.path().join("moved").join("final.wav");
+        fs::rename(&old, &new).unwrap();
+        let found = load_stored(&store, new.to_str().unwrap()).unwrap().unwrap();
+        assert_eq!(found.audio_path, old_path);
+        assert_eq!(found.full_text.as_deref(), Some("hello there"));
+        assert_eq!(found.audio_hash, metadata.audio_hash);
+    }
+
+    #[test]
+    fn test_legacy_sidecar_migrates_only_for_its_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = dir.path().join("store");
+        let wav = dir.path().join("take.wav");
+        let mp3 = dir.path().join("take.mp3");
+        fs::write(&wav, b"wav content").unwrap();
+        fs::write(&mp3, b"mp3 content").unwrap();
+        let wav_path = wav.to_str().unwrap();
+
+        // Old unversioned sidecar without a hash, written for take.wav
+        let legacy = serde_json::to_string(&empty_metadata(wav_path)).unwrap();
+        fs::write(get_metadata_path(wav_path), legacy).unwrap();
+
+        assert!(load_stored(&store, mp3.to_str().unwrap()).unwrap().is_none());
+        let mut migrated = load_stored(&store, wav_path).unwrap().unwrap();
+        let hash = content_hash(&wav).unwrap();
+        assert_eq!(migrated.audio_hash.as_deref(), Some(hash.as_str()));
+        // Loading has no side effects; the store entry is written on save
+        assert!(!store.join(format!("{}.json", hash)).exists());
+        save_stored(&store, wav_path, &mut migrated, false).unwrap();
+        assert!(store.join(format!("{}.json", hash)).exists());
+    }
+
+    #[test]
+    fn test_repair_words() {
+        let mut words = vec![
+            test_transcribed_word("b", "b", 1.0, 1.6),
+            test_transcribed_word("a", "a", 0.2, 1.2),
+            test_transcribed_word("nan", "nan", f64::NAN, 1.0),
+            test_transcribed_word("c", "c", 2.0, 1.9),
+            test_transcribed_word("d", "d", 2.5, 3.4),
+            test_transcribed_word("late", "late", 3.5, 3.8),
+        ];
+        let repairs = repair_words(&mut words, Some(3.0));
+        assert_eq!(repairs.len(), 3, "{:?}", repairs);
+        let spans: Vec<(&str, f64, f64)> = words.iter().map(|w| (w.id.as_str(), w.start, w.end)).collect();
+        assert_eq!(spans, vec![("a", 0.2, 1.0), ("b", 1.0, 1.6), ("c", 2.0, 2.0), ("d", 2.5, 3.0)]);
+
+        // Clean words are left alone
+        assert!(repair_words(&mut words, Some(3.0)).is_empty());
+    }
+
+    #[test]
+    fn test_migrates_unversioned_metadata() {
+        let legacy = json!({
+            "audioPath": "/old/take.wav",
+            "audioHash": null,
+            "words": [{ "id": "w1", "text": "hi", "start": 0.1, "end": 0.4, "confidence": 0.8 }],
+            "fullText": "hi",
+            "language": "en"
+        });
+        let metadata = migrate_metadata(legacy).unwrap();
+        assert_eq!(metadata.global_offset_ms, 0.0);
+        assert!(metadata.word_adjustments.is_empty());
+        assert_eq!(metadata.words.unwrap().len(), 1);
+
+        let current = json!({ "schemaVersion": METADATA_SCHEMA_VERSION, "metadata": empty_metadata("x.wav") });
+        assert_eq!(migrate_metadata(current).unwrap().full_text.as_deref(), Some("hello there"));
+
+        let future = json!({ "schemaVersion": METADATA_SCHEMA_VERSION + 1, "metadata": {} });
+        assert!(migrate_metadata(future).unwrap_err().contains("newer version"));
+    }
+
+    #[test]
+    fn test_corrupt_store_entry_is_quarantined() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = dir.path().join("store");
+        let audio = dir.path().join("take.wav");
+        fs::write(&audio, b"recording").unwrap();
+        let audio_path = audio.to_str().unwrap();
+
+        let mut metadata = empty_metadata(audio_path);
+        let entry = save_stored(&store, audio_path, &mut metadata, true).unwrap();
+        fs::write(&entry, "{ \"schemaVersion\": 1, \"metad").unwrap();
+
+        // The sidecar mirror is used instead
+        let loaded = load_stored(&store, audio_path).unwrap().unwrap();
+        assert_eq!(loaded.full_text.as_deref(), Some("hello there"));
+        assert!(!entry.exists());
+        let quarantined = fs::read_dir(&store).unwrap()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_name().to_string_lossy().contains(".corrupt-"))
+            .count();
+        assert_eq!(quarantined, 1);
+
+        // Without a mirror the error says where the file went
+        fs::write(&entry, "not json").unwrap();
+        fs::remove_file(get_metadata_path(audio_path)).unwrap();
+        let err = load_stored(&store, audio_path).unwrap_err();
+        assert!(err.contains("unreadable") && err.contains(".corrupt-"), "{}", err);
+        assert!(load_stored(&store, audio_path).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_corrupt_sidecar_without_store_entry_loads_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = dir.path().join("store");
+        let audio = dir.path().join("take.wav");
+        fs::write(&audio, b"recording").unwrap();
+        let audio_path = audio.to_str().unwrap();
+
+        fs::write(get_metadata_path(audio_path), "not json").unwrap();
+        assert!(load_stored(&store, audio_path).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_newer_schema_is_not_quarantined() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = dir.path().join("store");
+        let audio = dir.path().join("take.wav");
+        fs::write(&audio, b"recording").unwrap();
+        let audio_path = audio.to_str().unwrap();
+
+        let entry = store.join(format!("{}.json", content_hash(&audio).unwrap()));
+        fs::create_dir_all(&store).unwrap();
+        let future = json!({ "schemaVersion": 2, "metadata": { "audioPath": audio_path } });
+        fs::write(&entry, future.to_string()).unwrap();
+
+        let err = load_stored(&store, audio_path).unwrap_err();
+        assert!(err.contains("newer version"), "{}", err);
+        assert_eq!(fs::read_to_string(&entry).unwrap(), future.to_string());
+        assert_eq!(fs::read_dir(&store).unwrap().count(), 1);
+    }
+}
@@ -4,6 +4,19 @@ pub mod transcribe;
 pub mod moonshine;
 pub mod export;
 pub mod vad;
+pub mod cutlist;
+pub mod fillers;
+pub mod diarize;
+pub mod classify;
+pub mod chunking;
+pub mod alignment;
+pub mod engine;
+pub mod models;
+pub mod live_transcribe;
+pub mod subtitles;
+pub mod transcript_export;
+pub mod text_edit;
+pub mod redact;
 pub mod clean;
 pub mod metadata;
 pub mod recording;
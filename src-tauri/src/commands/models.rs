@@ -0,0 +1,292 @@
+//! Installed model management across engines: disk usage, delete, import from a
+//! local file/folder, and the default model per engine.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+use super::engine::{self, ModelLocations};
+use crate::services::path_service;
+
+/// Default model per engine ID, in the user data directory
+const DEFAULTS_FILENAME: &str = "model-defaults.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelDiskUsage {
+    pub engine: String,
+    pub name: String,
+    pub path: String,
+    pub bytes: u64,
+    /// False for bundled models, which live outside the models directories
+    pub removable: bool,
+    pub is_default: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiskUsageReport {
+    pub models: Vec<ModelDiskUsage>,
+    pub total_bytes: u64,
+}
+
+/// Model names end up in file names, so keep them to a safe set of characters
+pub(crate) fn validate_model_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid model name '{}': use letters, digits, '.', '-' and '_'", name))
+    }
+}
+
+/// Copy `source` to `dest` through a temporary file so a partly copied model is
+/// never picked up. Fails if `dest` already exists.
+pub(crate) fn install_copy(source: &Path, dest: &Path) -> Result<PathBuf, String> {
+    if dest.exists() {
+        return Err(format!("{:?} already exists; delete that model first", dest));
+    }
+    if let Some(parent) = dest.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create {:?}: {}", parent, e))?;
+    }
+    let file_name = dest.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let tmp = dest.with_file_name(format!(".{}.import", file_name));
+    std::fs::copy(source, &tmp).map_err(|e| {
+        let _ = std::fs::remove_file(&tmp);
+        format!("Failed to copy {:?}: {}", source, e)
+    })?;
+    std::fs::rename(&tmp, dest).map_err(|e| {
+        let _ = std::fs::remove_file(&tmp);
+        format!("Failed to move model into place: {}", e)
+    })?;
+    Ok(dest.to_path_buf())
+}
+
+/// Size of a file, or of everything under a directory
+pub(crate) fn path_size(path: &Path) -> u64 {
+    let Ok(meta) = std::fs::symlink_metadata(path) else { return 0 };
+    if !meta.is_dir() {
+        return meta.len();
+    }
+    std::fs::read_dir(path)
+        .map(|entries| entries.flatten().map(|e| path_size(&e.path())).sum())
+        .unwrap_or(0)
+}
+
+/// Directories models can be imported into and deleted from (not bundled resources)
+pub(crate) fn managed_dirs(custom_path: Option<&str>) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = custom_path.filter(|p| !p.is_empty()).map(PathBuf::from).into_iter().collect();
+    dirs.extend(path_service::get_models_dir().ok());
+    dirs
+}
+
+fn is_removable(path: &Path, managed: &[PathBuf]) -> bool {
+    let canonical = |p: &Path| p.canonicalize().unwrap_or_else(|_| p.to_path_buf());
+    let path = canonical(path);
+    managed.iter().any(|dir| {
+        let dir = canonical(dir);
+        path != dir && path.starts_with(&dir)
+    })
+}
+
+fn defaults_path() -> Result<PathBuf, String> {
+    let data_dir = path_service::get_user_data_dir()
+        .map_err(|e| format!("Path service error: {}", e))?;
+    Ok(data_dir.join(DEFAULTS_FILENAME))
+}
+
+fn load_defaults(path: &Path) -> BTreeMap<String, String> {
+    let Ok(content) = std::fs::read_to_string(path) else { return BTreeMap::new() };
+    serde_json::from_str(&content).unwrap_or_else(|e| {
+        log::warn!("Ignoring unreadable {:?}: {}", path, e);
+        BTreeMap::new()
+    })
+}
+
+fn save_defaults(path: &Path, defaults: &BTreeMap<String, String>) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(defaults)
+        .map_err(|e| format!("Failed to serialize model defaults: {}", e))?;
+    std::fs::write(path, json).map_err(|e| format!("Failed to write {:?}: {}", path, e))
+}
+
+/// Default model set for an engine with `set_default_model`
+pub(crate) fn default_model(engine_id: &str) -> Option<String> {
+    let path = defaults_path().ok()?;
+    load_defaults(&path).remove(engine_id)
+}
+
+/// Delete an installed model; returns the number of bytes freed.
+/// Bundled models can't be deleted.
+#[tauri::command]
+pub async fn delete_model(
+    app: AppHandle,
+    engine: String,
+    name: String,
+    models_path: Option<String>,
+) -> Result<u64, String> {
+    let engine = engine::find_engine(&engine)?;
+    let locations = ModelLocations::new(&app, models_path);
+    // Only look where models get installed: a bundled copy would be found first otherwise
+    let installed = managed_dirs(locations.custom_path())
+        .iter()
+        .find_map(|dir| engine.installed_model(&name, dir));
+    let Some(path) = installed else {
+        let path = engine.check_model(Some(&name), &locations)?;
+        return Err(format!("{:?} is bundled with the app and can't be deleted", path));
+    };
+
+    engine.unload_model(&path);
+    let bytes = path_size(&path);
+    let removed = if path.is_dir() { std::fs::remove_dir_all(&path) } else { std::fs::remove_file(&path) };
+    removed.map_err(|e| format!("Failed to delete {:?}: {}", path, e))?;
+
+    let defaults_file = defaults_path()?;
+    let mut defaults = load_defaults(&defaults_file);
+    if defaults.get(engine.id()) == Some(&name) {
+        defaults.remove(engine.id());
+        save_defaults(&defaults_file, &defaults)?;
+    }
+    log::info!("Deleted {} model {} ({:?}, {} bytes)", engine.id(), name, path, bytes);
+    Ok(bytes)
+}
+
+/// Disk usage of every installed model, per model and in total
+#[tauri::command]
+pub async fn get_model_disk_usage(
+    app: AppHandle,
+    models_path: Option<String>,
+) -> Result<DiskUsageReport, String> {
+    let locations = ModelLocations::new(&app, models_path);
+    let managed = managed_dirs(locations.custom_path());
+    let defaults = defaults_path().map(|p| load_defaults(&p)).unwrap_or_default();
+
+    let mut seen = HashSet::new();
+    let mut models = Vec::new();
+    for engine in engine::engines() {
+        for model in engine.list_models(&locations) {
+            let Some(path) = model.path.filter(|_| model.available) else { continue };
+            if !seen.insert(path.clone()) {
+                continue;
+            }
+            models.push(ModelDiskUsage {
+                engine: engine.id().to_string(),
+                is_default: defaults.get(engine.id()) == Some(&model.name),
+                name: model.name,
+                bytes: path_size(Path::new(&path)),
+                removable: is_removable(Path::new(&path), &managed),
+                path,
+            });
+        }
+    }
+    let total_bytes = models.iter().map(|m| m.bytes).sum();
+    Ok(DiskUsageReport { models, total_bytes })
+}
+
+/// Import a model from a local file (Whisper GGML) or folder (Moonshine ONNX set)
+/// into the models directory. Returns the installed path.
+#[tauri::command]
+pub async fn import_model(
+    app: AppHandle,
+    engine: String,
+    source_path: String,
+    name: Option<String>,
+    models_path: Option<String>,
+) -> Result<String, String> {
+    let engine = engine::find_engine(&engine)?;
+    let locations = ModelLocations::new(&app, models_path);
+    let target = managed_dirs(locations.custom_path())
+        .into_iter()
+        .next()
+        .ok_or("No models directory available")?;
+    let source = Path::new(&source_path);
+    if !source.exists() {
+        return Err(format!("{} does not exist", source_path));
+    }
+
+    let installed = engine.install_model(source, name.as_deref(), &target)?;
+    log::info!("Imported {} model from {} to {:?}", engine.id(), source_path, installed);
+    Ok(installed.to_string_lossy().to_string())
+}
+
+/// Set (or with `model: None`, clear) the model an engine uses when none is given
+#[tauri::command]
+pub async fn set_default_model(
+    app: AppHandle,
+    engine: String,
+    model: Option<String>,
+    models_path: Option<String>,
+) -> Result<(), String> {
+    let engine = engine::find_engine(&engine)?;
+    if let Some(name) = model.as_deref() {
+        engine.check_model(Some(name), &ModelLocations::new(&app, models_path))?;
+    }
+
+    let defaults_file = defaults_path()?;
+    let mut defaults = load_defaults(&defaults_file);
+    match model {
+        Some(name) => defaults.insert(engine.id().to_string(), name),
+        None => defaults.remove(engine.id()),
+    };
+    save_defaults(&defaults_file, &defaults)
+}
+
+/// Default model per engine ID
+#[tauri::command]
+pub async fn get_default_models() -> Result<BTreeMap<String, String>, String> {
+    Ok(load_defaults(&defaults_path()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_install_copy_and_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("source.bin");
+        std::fs::write(&source, vec![7u8; 1000]).unwrap();
+
+        let dest = dir.path().join("models").join("ggml-custom.bin");
+        assert_eq!(install_copy(&source, &dest).unwrap(), dest);
+        assert_eq!(std::fs::read(&dest).unwrap(), vec![7u8; 1000]);
+        assert!(install_copy(&source, &dest).unwrap_err().contains("already exists"));
+
+        std::fs::write(dir.path().join("models").join("notes.txt"), "hello").unwrap();
+        assert_eq!(path_size(&dir.path().join("models")), 1005);
+        assert_eq!(path_size(&dir.path().join("missing")), 0);
+    }
+
+    #[test]
+    fn test_defaults_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(DEFAULTS_FILENAME);
+        assert!(load_defaults(&path).is_empty());
+
+        let mut defaults = BTreeMap::new();
+        defaults.insert("whisper".to_string(), "small.en".to_string());
+        save_defaults(&path, &defaults).unwrap();
+        assert_eq!(load_defaults(&path), defaults);
+
+        std::fs::write(&path, "{not json").unwrap();
+        assert!(load_defaults(&path).is_empty());
+    }
+
+    #[test]
+    fn test_removable_and_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let managed = vec![dir.path().join("models")];
+        std::fs::create_dir_all(&managed[0]).unwrap();
+        std::fs::write(managed[0].join("ggml-base.bin"), b"model").unwrap();
+        assert!(is_removable(&managed[0].join("ggml-base.bin"), &managed));
+        assert!(!is_removable(&managed[0], &managed));
+        assert!(!is_removable(&dir.path().join("resources/ggml-base.bin"), &managed));
+
+        assert!(validate_model_name("large-v3_q5.en").is_ok());
+        assert!(validate_model_name("../escape").is_err());
+        assert!(validate_model_name("a/b").is_err());
+        assert!(validate_model_name("").is_err());
+    }
+}
@@ -6,12 +6,19 @@ use ort::session::Session;
 use ort::value::TensorRef;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::Path;
-use std::sync::Mutex;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::Instant;
 
 use tauri::Manager;
-use super::transcribe::{TranscriptionMetrics, TranscriptionResult, Word};
+use super::alignment;
+use super::chunking::{self, SampleSource, Stream16k, WindowConfig, Windower};
+use super::engine::{
+    EngineCapabilities, EngineModel, ModelLocations, ProgressReporter, TranscriptionEngine, TranscriptionJob,
+};
+use super::transcribe::{TranscriptionMetrics, TranscriptionResult, Word, CANCELLED_MESSAGE};
+use crate::services::path_service;
 
 /// Cached ONNX sessions to avoid reloading models on every transcription (~10s load time).
 /// Key: model directory path string. Value: (encoder, decoder) sessions.
@@ -265,62 +272,16 @@ fn variant_config(name: &str) -> VariantConfig {
 
 // ── Model path resolution ──
 
-fn get_models_dir() -> Result<std::path::PathBuf, String> {
-    crate::services::path_service::get_models_dir()
-}
-
 fn find_moonshine_model(
     variant: &str,
     custom_path: Option<&str>,
     resource_dir: Option<&std::path::Path>,
 ) -> Result<std::path::PathBuf, String> {
     let subdir = format!("moonshine/{}", variant);
-
-    if let Some(custom) = custom_path {
-        if !custom.is_empty() {
-            let dir = Path::new(custom).join(&subdir);
-            if is_valid_moonshine_dir(&dir) {
-                log::info!("Found moonshine {} at custom path: {:?}", variant, dir);
-                return Ok(dir);
-            }
-        }
-    }
-
-    // Check bundled resources (Tauri resource_dir — set in release builds)
-    if let Some(res_dir) = resource_dir {
-        let dir = res_dir.join("models").join(&subdir);
-        if is_valid_moonshine_dir(&dir) {
-            log::info!("Found moonshine {} at bundled resources: {:?}", variant, dir);
-            return Ok(dir);
-        }
-    }
-
-    // Check adjacent to executable (Flatpak, portable installs)
-    if let Ok(exe) = std::env::current_exe() {
-        if let Some(exe_dir) = exe.parent() {
-            let dir = exe_dir.join("models").join(&subdir);
-            if is_valid_moonshine_dir(&dir) {
-                log::info!("Found moonshine {} next to executable: {:?}", variant, dir);
-                return Ok(dir);
-            }
-        }
-    }
-
-    // Dev mode: check src-tauri/resources/models via CARGO_MANIFEST_DIR
-    let dev_dir = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
-        .join("resources")
-        .join("models")
-        .join(&subdir);
-    if is_valid_moonshine_dir(&dev_dir) {
-        log::info!("Found moonshine {} at dev path: {:?}", variant, dev_dir);
-        return Ok(dev_dir);
-    }
-
-    // App data directory (user-installed models)
-    if let Ok(models_dir) = get_models_dir() {
+    for (kind, models_dir) in path_service::model_search_dirs(custom_path, resource_dir) {
         let dir = models_dir.join(&subdir);
         if is_valid_moonshine_dir(&dir) {
-            log::info!("Found moonshine {} at app data: {:?}", variant, dir);
+            log::info!("Found moonshine {} at {}: {:?}", variant, kind, dir);
             return Ok(dir);
         }
     }
@@ -332,9 +293,42 @@ fn find_moonshine_model(
 }
 
 fn is_valid_moonshine_dir(dir: &Path) -> bool {
-    dir.join("encoder_model.onnx").exists()
-        && dir.join("decoder_model_merged.onnx").exists()
-        && dir.join("tokenizer.json").exists()
+    MOONSHINE_FILES.iter().all(|f| dir.join(f).exists())
+}
+
+/// Files making up a Moonshine model directory
+const MOONSHINE_FILES: &[&str] = &["encoder_model.onnx", "decoder_model_merged.onnx", "tokenizer.json"];
+
+/// Check the files in a Moonshine model directory look like what we expect: ONNX
+/// protobufs (starting with the `ir_version` field tag) and a JSON tokenizer
+fn validate_moonshine_files(dir: &Path) -> Result<(), String> {
+    if !is_valid_moonshine_dir(dir) {
+        return Err(format!("{:?} must contain {}", dir, MOONSHINE_FILES.join(" + ")));
+    }
+    for onnx in &MOONSHINE_FILES[..2] {
+        let path = dir.join(onnx);
+        let mut tag = [0u8; 1];
+        std::fs::File::open(&path)
+            .and_then(|mut f| std::io::Read::read_exact(&mut f, &mut tag))
+            .map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+        if tag[0] != 0x08 {
+            return Err(format!("{} is not an ONNX model", onnx));
+        }
+    }
+    let tokenizer = std::fs::read_to_string(dir.join("tokenizer.json"))
+        .map_err(|e| format!("Failed to read tokenizer.json: {}", e))?;
+    serde_json::from_str::<serde_json::Value>(&tokenizer)
+        .map_err(|e| format!("tokenizer.json is not valid JSON: {}", e))?;
+    Ok(())
+}
+
+/// The named model, the default one set for Moonshine, or else the first installed one.
+/// Returns (variant name, model directory).
+pub(crate) fn locate_moonshine_model(model: Option<&str>, locations: &ModelLocations) -> Result<(String, PathBuf), String> {
+    match model.map(str::to_string).or_else(|| super::models::default_model("moonshine")) {
+        Some(name) => MoonshineEngine.check_model(Some(&name), locations).map(|dir| (name, dir)),
+        None => find_any_moonshine_model(locations.custom_path(), locations.resource_dir()),
+    }
 }
 
 fn find_any_moonshine_model(custom_path: Option<&str>, resource_dir: Option<&std::path::Path>) -> Result<(String, std::path::PathBuf), String> {
@@ -504,7 +498,8 @@ fn run_moonshine_inference(
     Ok(tokens)
 }
 
-fn init_session(path: &Path) -> Result<Session, String> {
+/// Build a CPU ONNX Runtime session (shared with other local ONNX models, e.g. diarization)
+pub(crate) fn init_session(path: &Path) -> Result<Session, String> {
     let providers = vec![CPUExecutionProvider::default().build()];
 
     log::info!("[ORT] Creating session builder...");
@@ -533,73 +528,58 @@ fn init_session(path: &Path) -> Result<Session, String> {
 
 // ── Chunked transcription for long files ──
 
-fn transcribe_chunked(
+/// 30s windows with 1s of shared context, cut at the quietest point of their last 5s
+const MOONSHINE_WINDOW: WindowConfig = WindowConfig { window: 30.0, overlap: 1.0, search: 5.0 };
+
+/// Transcribe a sample stream window by window. `offset` is the time of the
+/// stream's first sample, so word times come out absolute. `on_window` gets each
+/// window's kept words and the audio duration transcribed so far.
+/// Returns (text, words, audio duration in seconds).
+pub(crate) fn transcribe_chunked<S: SampleSource>(
     model_dir: &Path,
     variant_name: &str,
-    samples: &[f32],
-) -> Result<(String, Vec<Word>), String> {
-    let total_duration = samples.len() as f64 / SAMPLE_RATE as f64;
-
+    source: S,
+    offset: f64,
+    config: WindowConfig,
+    cancel: &AtomicBool,
+    mut on_window: impl FnMut(&[Word], f64),
+) -> Result<(String, Vec<Word>, f64), String> {
     let tokenizer = MoonshineTokenizer::load(model_dir)?;
     let (mut encoder, mut decoder) = get_or_load_sessions(model_dir)?;
 
-    let result = (|| -> Result<(String, Vec<Word>), String> {
-        if total_duration <= 60.0 {
-            let t_inf = Instant::now();
-            let tokens = run_moonshine_inference(&mut encoder, &mut decoder, variant_name, samples)?;
-            log::info!("Moonshine inference: {} tokens in {}ms", tokens.len(), t_inf.elapsed().as_millis());
-            log::info!("Moonshine tokens (first 20): {:?}", &tokens[..tokens.len().min(20)]);
-            let text = tokenizer.decode(&tokens)?;
-            log::info!("Moonshine decoded text ({} chars): {:?}", text.len(), &text[..text.len().min(200)]);
-            let words = estimate_word_timestamps(&text, 0.0, total_duration);
-            log::info!("Moonshine estimated {} words", words.len());
-            return Ok((text, words));
-        }
-
-        let chunk_samples = 30 * SAMPLE_RATE as usize;
-        let overlap_samples = SAMPLE_RATE as usize;
-        let mut all_text = String::new();
+    let result = (|| -> Result<(String, Vec<Word>, f64), String> {
+        let mut windows = Windower::new(source, config).starting_at(offset);
         let mut all_words: Vec<Word> = Vec::new();
-        let mut offset = 0usize;
+        let mut duration = 0.0;
 
-        while offset < samples.len() {
-            let end = (offset + chunk_samples).min(samples.len());
-            let chunk = &samples[offset..end];
-            let chunk_duration = chunk.len() as f64 / SAMPLE_RATE as f64;
-            let chunk_start_time = offset as f64 / SAMPLE_RATE as f64;
-
-            if chunk_duration < 0.1 {
-                break;
+        while let Some(window) = windows.next_window()? {
+            if cancel.load(Ordering::Relaxed) {
+                return Err(CANCELLED_MESSAGE.to_string());
+            }
+            duration = window.start + window.duration() - offset;
+            if window.duration() < 0.1 {
+                continue;
             }
 
             log::info!(
-                "Moonshine chunk: {:.1}s - {:.1}s ({:.1}s)",
-                chunk_start_time,
-                chunk_start_time + chunk_duration,
-                chunk_duration
+                "Moonshine window: {:.1}s - {:.1}s ({:.1}s)",
+                window.start,
+                window.start + window.duration(),
+                window.duration()
             );
 
-            let tokens = run_moonshine_inference(&mut encoder, &mut decoder, variant_name, chunk)?;
-            let chunk_text = tokenizer.decode(&tokens)?;
-
-            if !chunk_text.is_empty() {
-                if !all_text.is_empty() {
-                    all_text.push(' ');
-                }
-                all_text.push_str(&chunk_text);
-
-                let chunk_words =
-                    estimate_word_timestamps(&chunk_text, chunk_start_time, chunk_duration);
-                all_words.extend(chunk_words);
-            }
+            let t_inf = Instant::now();
+            let tokens = run_moonshine_inference(&mut encoder, &mut decoder, variant_name, &window.samples)?;
+            let window_text = tokenizer.decode(&tokens)?;
+            log::info!("Moonshine inference: {} tokens in {}ms", tokens.len(), t_inf.elapsed().as_millis());
 
-            if end >= samples.len() {
-                break;
-            }
-            offset = end - overlap_samples;
+            let window_words = chunking::keep_owned(&window, align_window_words(&window_text, &window.samples, window.start));
+            on_window(&window_words, duration);
+            all_words.extend(window_words);
         }
 
-        Ok((all_text, all_words))
+        let all_text = all_words.iter().map(|w| w.text.as_str()).collect::<Vec<_>>().join(" ");
+        Ok((all_text, all_words, duration))
     })();
 
     // Return sessions to cache regardless of success/failure
@@ -608,37 +588,25 @@ fn transcribe_chunked(
     result
 }
 
-/// Estimate word-level timestamps by distributing duration proportionally by character count.
-fn estimate_word_timestamps(text: &str, start_time: f64, duration: f64) -> Vec<Word> {
-    let text_words: Vec<&str> = text.split_whitespace().collect();
-    if text_words.is_empty() {
-        return Vec::new();
-    }
-
-    let total_chars: usize = text_words.iter().map(|w| w.len()).sum();
-    if total_chars == 0 {
-        return Vec::new();
-    }
-
-    let mut words = Vec::with_capacity(text_words.len());
-    let mut cursor = start_time;
+/// Confidence reported for Moonshine words (the decoder is greedy and doesn't score words)
+const MOONSHINE_WORD_CONFIDENCE: f64 = 0.7;
 
-    for word_text in &text_words {
-        let word_fraction = word_text.len() as f64 / total_chars as f64;
-        let word_duration = duration * word_fraction;
+/// Build words for a window's text with boundaries aligned to the window's audio.
+fn align_window_words(text: &str, samples: &[f32], start_time: f64) -> Vec<Word> {
+    let text_words: Vec<&str> = text.split_whitespace().collect();
+    let spans = alignment::align_words(&text_words, samples, SAMPLE_RATE);
 
-        words.push(Word {
+    text_words
+        .iter()
+        .zip(spans)
+        .map(|(word_text, (start, end))| Word {
             id: uuid::Uuid::new_v4().to_string(),
             text: word_text.to_string(),
-            start: cursor,
-            end: cursor + word_duration,
-            confidence: 0.7, // Lower confidence — timestamps are estimated
-        });
-
-        cursor += word_duration;
-    }
-
-    words
+            start: start_time + start,
+            end: start_time + end,
+            confidence: MOONSHINE_WORD_CONFIDENCE,
+        })
+        .collect()
 }
 
 // ── Tauri Commands ──
@@ -648,25 +616,53 @@ pub async fn transcribe_moonshine(
     path: String,
     models_path: Option<String>,
     app: tauri::AppHandle,
+    start_time: Option<f64>,
+    end_time: Option<f64>,
 ) -> Result<TranscriptionResult, String> {
-    let total_start = Instant::now();
-    let audio_path = Path::new(&path);
     let custom_path = models_path.as_deref();
     let resource_dir = app.path().resource_dir().ok();
 
-    log::info!("Moonshine transcribing: {}", path);
+    log::info!("Moonshine transcribing: {} (range: {:?} - {:?})", path, start_time, end_time);
 
     let (variant_name, model_dir) = find_any_moonshine_model(custom_path, resource_dir.as_deref())?;
+    transcribe_moonshine_blocking(&path, &variant_name, &model_dir, start_time, end_time, None, &AtomicBool::new(false))
+}
+
+/// Transcribe a file (or a range of it) with a located Moonshine model
+fn transcribe_moonshine_blocking(
+    path: &str,
+    variant_name: &str,
+    model_dir: &Path,
+    start_time: Option<f64>,
+    end_time: Option<f64>,
+    progress: Option<&ProgressReporter>,
+    cancel: &AtomicBool,
+) -> Result<TranscriptionResult, String> {
+    let total_start = Instant::now();
+    let audio_path = Path::new(path);
     log::info!("Using moonshine model: {} at {:?}", variant_name, model_dir);
 
+    // Audio is streamed window by window, so only the current window is in memory
+    if let Some(progress) = progress {
+        progress.report("loading", 0.0);
+    }
     let load_start = Instant::now();
-    let samples = super::transcribe::load_audio_16khz_pub(audio_path)?;
-    let audio_duration_secs = samples.len() as f64 / 16000.0;
+    let stream = Stream16k::open_range(audio_path, start_time, end_time)?;
+    let expected_secs = stream.duration_secs();
     let load_time_ms = load_start.elapsed().as_millis() as u64;
-    log::info!("Loaded {} samples at 16kHz ({:.1}s) in {}ms", samples.len(), audio_duration_secs, load_time_ms);
 
+    if let Some(progress) = progress {
+        progress.report("decoding", 0.0);
+    }
     let inference_start = Instant::now();
-    let (text, words) = transcribe_chunked(&model_dir, &variant_name, &samples)?;
+    let (text, words, audio_duration_secs) = transcribe_chunked(
+        model_dir, variant_name, stream, start_time.unwrap_or(0.0), MOONSHINE_WINDOW, cancel,
+        |_, duration| {
+            if let (Some(progress), Some(expected)) = (progress, expected_secs.filter(|d| *d > 0.0)) {
+                progress.report("decoding", (duration / expected).min(1.0) as f32);
+            }
+        },
+    )?;
     let inference_time_ms = inference_start.elapsed().as_millis() as u64;
 
     let total_time_ms = total_start.elapsed().as_millis() as u64;
@@ -683,6 +679,9 @@ pub async fn transcribe_moonshine(
         words,
         text,
         language: "en".to_string(),
+        language_probability: None,
+        task: super::transcribe::TASK_TRANSCRIBE.to_string(),
+        segments: Vec::new(),
         metrics: Some(TranscriptionMetrics {
             engine: "moonshine".to_string(),
             model_name: format!("moonshine-{}", variant_name),
@@ -713,20 +712,124 @@ pub async fn list_moonshine_models(
     custom_path: Option<String>,
     app: tauri::AppHandle,
 ) -> Result<Vec<MoonshineModelInfo>, String> {
-    let path_ref = custom_path.as_deref();
     let resource_dir = app.path().resource_dir().ok();
-    let mut models = Vec::new();
-
-    for (name, _subdir, enc_mb, dec_mb) in MOONSHINE_MODELS {
-        let found = find_moonshine_model(name, path_ref, resource_dir.as_deref()).ok();
-        models.push(MoonshineModelInfo {
-            name: name.to_string(),
-            available: found.is_some(),
-            path: found.map(|p| p.to_string_lossy().to_string()),
-            encoder_size_mb: *enc_mb,
-            decoder_size_mb: *dec_mb,
-        });
+    Ok(moonshine_models(custom_path.as_deref(), resource_dir.as_deref()))
+}
+
+fn moonshine_models(path_ref: Option<&str>, resource_dir: Option<&Path>) -> Vec<MoonshineModelInfo> {
+    MOONSHINE_MODELS
+        .iter()
+        .map(|(name, _subdir, enc_mb, dec_mb)| {
+            let found = find_moonshine_model(name, path_ref, resource_dir).ok();
+            MoonshineModelInfo {
+                name: name.to_string(),
+                available: found.is_some(),
+                path: found.map(|p| p.to_string_lossy().to_string()),
+                encoder_size_mb: *enc_mb,
+                decoder_size_mb: *dec_mb,
+            }
+        })
+        .collect()
+}
+
+/// Moonshine (English-only ONNX models) behind the common engine interface
+pub struct MoonshineEngine;
+
+impl TranscriptionEngine for MoonshineEngine {
+    fn id(&self) -> &'static str {
+        "moonshine"
     }
 
-    Ok(models)
+    fn name(&self) -> &'static str {
+        "Moonshine"
+    }
+
+    fn capabilities(&self) -> EngineCapabilities {
+        EngineCapabilities {
+            languages: vec!["en".to_string()],
+            language_detection: false,
+            translation: false,
+            vocabulary: false,
+            decoding_options: false,
+        }
+    }
+
+    fn list_models(&self, locations: &ModelLocations) -> Vec<EngineModel> {
+        moonshine_models(locations.custom_path(), locations.resource_dir())
+            .into_iter()
+            .map(|m| EngineModel {
+                name: m.name,
+                available: m.available,
+                path: m.path,
+                size_mb: m.encoder_size_mb + m.decoder_size_mb,
+                download_url: None,
+            })
+            .collect()
+    }
+
+    fn check_model(&self, model: Option<&str>, locations: &ModelLocations) -> Result<PathBuf, String> {
+        match model {
+            Some(name) if !MOONSHINE_MODELS.iter().any(|(known, ..)| *known == name) => {
+                let known: Vec<&str> = MOONSHINE_MODELS.iter().map(|(n, ..)| *n).collect();
+                Err(format!("Unknown Moonshine model '{}' (available: {})", name, known.join(", ")))
+            }
+            Some(name) => find_moonshine_model(name, locations.custom_path(), locations.resource_dir()),
+            None => find_any_moonshine_model(locations.custom_path(), locations.resource_dir()).map(|(_, dir)| dir),
+        }
+    }
+
+    fn transcribe(
+        &self,
+        job: &TranscriptionJob,
+        progress: &ProgressReporter,
+        cancel: &Arc<AtomicBool>,
+    ) -> Result<TranscriptionResult, String> {
+        let (variant_name, model_dir) = locate_moonshine_model(job.options.model.as_deref(), &job.locations)?;
+        transcribe_moonshine_blocking(
+            &job.path, &variant_name, &model_dir, job.start_time, job.end_time, Some(progress), cancel,
+        )
+    }
+
+    fn install_model(&self, source: &Path, name: Option<&str>, models_dir: &Path) -> Result<PathBuf, String> {
+        validate_moonshine_files(source)?;
+        let name = match name {
+            Some(name) => name.to_string(),
+            None => source.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+        };
+        let Some((_, subdir, _, _)) = MOONSHINE_MODELS.iter().find(|(known, ..)| *known == name) else {
+            let known: Vec<&str> = MOONSHINE_MODELS.iter().map(|(n, ..)| *n).collect();
+            return Err(format!(
+                "Unknown Moonshine model '{}'; pass one of {} as the name",
+                name, known.join(", ")
+            ));
+        };
+
+        let dest = models_dir.join("moonshine").join(subdir);
+        if dest.exists() {
+            return Err(format!("{:?} already exists; delete that model first", dest));
+        }
+        std::fs::create_dir_all(&dest).map_err(|e| format!("Failed to create {:?}: {}", dest, e))?;
+        for file in MOONSHINE_FILES {
+            if let Err(e) = super::models::install_copy(&source.join(file), &dest.join(file)) {
+                let _ = std::fs::remove_dir_all(&dest);
+                return Err(e);
+            }
+        }
+        Ok(dest)
+    }
+
+    fn installed_model(&self, name: &str, models_dir: &Path) -> Option<PathBuf> {
+        let (_, subdir, ..) = MOONSHINE_MODELS.iter().find(|(known, ..)| *known == name)?;
+        let dir = models_dir.join("moonshine").join(subdir);
+        is_valid_moonshine_dir(&dir).then_some(dir)
+    }
+
+    fn unload_model(&self, path: &Path) {
+        let key = path.to_string_lossy().to_string();
+        let mut guard = SESSION_CACHE.get_or_init(|| Mutex::new(None)).lock().expect("SESSION_CACHE mutex poisoned");
+        if guard.as_ref().is_some_and(|(cached, _, _)| *cached == key) {
+            guard.take();
+            log::info!("Unloaded moonshine sessions for {}", key);
+        }
+    }
 }
@@ -11,7 +11,7 @@ pub mod system_audio;
 
 // Re-export all public types so external callers remain unchanged
 pub use types::*;
-pub use ring_buffer::{RecordingRingBuffer, PreRecordBuffer, PRE_RECORD_SECONDS};
+pub use ring_buffer::{RecordingRingBuffer, PreRecordBuffer, TapChunk, PRE_RECORD_SECONDS};
 pub use wav_writer::{
     segment_path, spawn_wav_writer_thread, stereo_wav_to_mono_streaming,
     patch_wav_header_if_needed, estimate_wav_duration, read_wav_format,
@@ -257,6 +257,27 @@ impl RecordingManager {
             false
         }
     }
+
+    /// Send a copy of a recording session's drained samples to `tap` until the
+    /// recording stops. Returns the session's (sample rate, channels).
+    pub(crate) fn attach_tap(&self, session_id: &str, tap: std::sync::mpsc::SyncSender<TapChunk>) -> Result<(u32, u16), String> {
+        let sessions = self.sessions.lock().map_err(|_| "Session lock poisoned".to_string())?;
+        let session = sessions.get(session_id)
+            .ok_or_else(|| format!("No recording in progress for session '{}'", session_id))?;
+        let ring = session.ring_buffer.as_ref()
+            .ok_or_else(|| format!("Session '{}' has no ring buffer", session_id))?;
+        ring.set_tap(Some(tap));
+        Ok((session.sample_rate, session.channels))
+    }
+
+    /// Stop feeding a session's tap (no-op if the session already stopped)
+    pub(crate) fn detach_tap(&self, session_id: &str) {
+        if let Ok(sessions) = self.sessions.lock() {
+            if let Some(ring) = sessions.get(session_id).and_then(|s| s.ring_buffer.as_ref()) {
+                ring.set_tap(None);
+            }
+        }
+    }
 }
 
 
@@ -1875,6 +1896,39 @@ mod tests {
         assert_eq!(ring.capacity, 256);
     }
 
+    #[test]
+    fn ring_buffer_tap_copies_with_bad_channel_fixup() {
+        let ring = RecordingRingBuffer::new(16).with_channels(2);
+        // No tap attached: nothing happens
+        ring.feed_tap(0, 4);
+
+        let (tx, rx) = std::sync::mpsc::sync_channel(1);
+        ring.set_tap(Some(tx));
+        // Frames (L, R) = (10, 11), (12, 13) starting at ring position 14 (wraps)
+        for (i, v) in [10.0f32, 11.0, 12.0, 13.0].iter().enumerate() {
+            unsafe { *ring.data_ptr.add((14 + i) & ring.mask) = *v; }
+        }
+        ring.feed_tap(14, 4);
+        let chunk = rx.try_recv().unwrap();
+        assert_eq!(chunk.position, 14);
+        assert_eq!(chunk.samples, vec![10.0, 11.0, 12.0, 13.0]);
+
+        // Channel 0 flagged bad: left takes the right channel's samples
+        ring.bad_channel.store(1, Ordering::Relaxed);
+        ring.feed_tap(14, 4);
+        assert_eq!(rx.try_recv().unwrap().samples, vec![11.0, 11.0, 13.0, 13.0]);
+
+        // A full channel drops the chunk instead of blocking the writer
+        ring.feed_tap(14, 4);
+        ring.feed_tap(14, 4);
+        assert_eq!(ring.tap_dropped_count.load(Ordering::Relaxed), 1);
+        assert!(rx.try_recv().is_ok());
+
+        // Detaching closes the channel
+        ring.set_tap(None);
+        assert!(rx.recv().is_err());
+    }
+
     // ── Overrun Detection ──
 
     #[test]
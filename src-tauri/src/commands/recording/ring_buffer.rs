@@ -1,7 +1,17 @@
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{SyncSender, TrySendError};
+use std::sync::Mutex;
 
 // ── Lock-free ring buffer for realtime-safe recording ──
 
+/// Samples drained by the writer thread, copied out for a live consumer
+pub struct TapChunk {
+    /// Ring position (interleaved samples since the recording started) of `samples[0]`
+    pub position: usize,
+    /// Interleaved samples, with any bad channel already replaced
+    pub samples: Vec<f32>,
+}
+
 /// SPSC ring buffer: audio callback (producer) writes samples lock-free,
 /// dedicated writer thread (consumer) drains to disk.
 pub struct RecordingRingBuffer {
@@ -25,6 +35,11 @@ pub struct RecordingRingBuffer {
     pub overrun_count: AtomicUsize,
     /// High-water mark of ring buffer usage (samples)
     pub max_fill_level: AtomicUsize,
+    /// Live consumer of drained samples (e.g. live transcription). Only the writer
+    /// thread and commands lock this, never the audio callback.
+    pub tap: Mutex<Option<SyncSender<TapChunk>>>,
+    /// Number of tap chunks dropped because the live consumer fell behind
+    pub tap_dropped_count: AtomicUsize,
 }
 
 unsafe impl Send for RecordingRingBuffer {}
@@ -48,6 +63,8 @@ impl RecordingRingBuffer {
             bad_channel: AtomicUsize::new(0),
             overrun_count: AtomicUsize::new(0),
             max_fill_level: AtomicUsize::new(0),
+            tap: Mutex::new(None),
+            tap_dropped_count: AtomicUsize::new(0),
         }
     }
 
@@ -55,6 +72,45 @@ impl RecordingRingBuffer {
         self.channels = channels;
         self
     }
+
+    /// Attach (or with None, detach) the live consumer of drained samples.
+    /// Detaching closes the channel, which tells the consumer the stream ended.
+    pub fn set_tap(&self, tap: Option<SyncSender<TapChunk>>) {
+        if let Ok(mut guard) = self.tap.lock() {
+            *guard = tap;
+        }
+    }
+
+    /// Copy `count` samples starting at ring position `rp` to the tap, if one is attached.
+    /// Called by the writer thread before it releases the samples; never blocks on a
+    /// consumer that fell behind, the chunk is dropped (and counted) instead.
+    pub fn feed_tap(&self, rp: usize, count: usize) {
+        let Ok(mut guard) = self.tap.lock() else { return };
+        let Some(tap) = guard.as_ref() else { return };
+        if count == 0 {
+            return;
+        }
+
+        let bad_ch = self.bad_channel.load(Ordering::Relaxed);
+        let mut samples = Vec::with_capacity(count);
+        for i in 0..count {
+            // Same fixup as the writer: take the good channel's sample for the bad one
+            let pos = match (self.channels, bad_ch, (rp + i) % 2) {
+                (2, 1, 0) => rp + i + 1,
+                (2, 2, 1) => rp + i - 1,
+                _ => rp + i,
+            };
+            samples.push(unsafe { *self.data_ptr.add(pos & self.mask) });
+        }
+        match tap.try_send(TapChunk { position: rp, samples }) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => {
+                self.tap_dropped_count.fetch_add(1, Ordering::Relaxed);
+            }
+            // Consumer went away
+            Err(TrySendError::Disconnected(_)) => *guard = None,
+        }
+    }
 }
 
 // ── Pre-record circular buffer ──
@@ -129,6 +129,7 @@ pub fn spawn_wav_writer_thread(
                 if channels == 2 && bad_ch > 0 {
                     // Write with bad-channel fixup (replace bad channel with good one)
                     let pairs = available / 2;
+                    ring.feed_tap(rp, pairs * 2);
                     for i in 0..pairs {
                         let idx0 = (rp + i * 2) & ring.mask;
                         let idx1 = (rp + i * 2 + 1) & ring.mask;
@@ -189,6 +190,7 @@ pub fn spawn_wav_writer_thread(
                     segment_data_bytes += consumed * 4;
                 } else {
                     // Normal path: write all samples directly
+                    ring.feed_tap(rp, available);
                     for i in 0..available {
                         let idx = (rp + i) & ring.mask;
                         let sample = unsafe { *ring.data_ptr.add(idx) };
@@ -223,6 +225,13 @@ pub fn spawn_wav_writer_thread(
             let wp = ring.write_pos.load(std::sync::atomic::Ordering::Acquire);
             let rp = ring.read_pos.load(std::sync::atomic::Ordering::Relaxed);
             let remaining = wp.wrapping_sub(rp);
+            ring.feed_tap(rp, remaining);
+            // Closing the tap tells a live consumer the recording ended
+            ring.set_tap(None);
+            let tap_dropped = ring.tap_dropped_count.load(std::sync::atomic::Ordering::Relaxed);
+            if tap_dropped > 0 {
+                log::warn!("Live tap fell behind: {} chunks dropped", tap_dropped);
+            }
             for i in 0..remaining {
                 let idx = (rp + i) & ring.mask;
                 let sample = unsafe { *ring.data_ptr.add(idx) };
@@ -0,0 +1,266 @@
+//! Redaction (bleeping) of time ranges or transcript words in EDL exports
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use super::cutlist;
+use super::export::ExportEDLTrack;
+use super::fillers::{self, FillerOptions};
+use super::transcribe::Word;
+
+/// Bleep tone frequency and level (-12 dBFS)
+const TONE_HZ: f64 = 1000.0;
+const TONE_LEVEL: f32 = 0.25;
+/// Noise bed level (-40 dBFS)
+const NOISE_LEVEL: f32 = 0.01;
+/// Default fade into and out of a redaction, in seconds
+const DEFAULT_REDACTION_FADE: f64 = 0.005;
+
+fn default_redaction_fade() -> f64 {
+    DEFAULT_REDACTION_FADE
+}
+
+/// What replaces redacted audio
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RedactionStyle {
+    #[default]
+    Tone,
+    Silence,
+    Noise,
+}
+
+/// A timeline range (output seconds) to redact
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RedactionRange {
+    pub start: f64,
+    pub end: f64,
+    #[serde(default)]
+    pub style: RedactionStyle,
+}
+
+/// Redactions applied to an EDL export, as timeline ranges and/or transcript words
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExportRedaction {
+    #[serde(default)]
+    pub ranges: Vec<RedactionRange>,
+    /// Transcript words to redact, looked up in `words` and mapped through the EDL's clips
+    #[serde(default)]
+    pub word_ids: Vec<String>,
+    #[serde(default)]
+    pub words: Vec<Word>,
+    /// Source file the words' times refer to (default: the first track's source)
+    #[serde(default)]
+    pub source_path: Option<String>,
+    /// Style for redacted words
+    #[serde(default)]
+    pub style: RedactionStyle,
+    /// Fade into and out of each range, in seconds (the range itself is fully replaced)
+    #[serde(default = "default_redaction_fade")]
+    pub fade: f64,
+}
+
+/// A transcript phrase matching one of the requested terms
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RedactionMatch {
+    pub word_ids: Vec<String>,
+    pub text: String,
+    pub start: f64,
+    pub end: f64,
+}
+
+/// Turn word redactions into timeline ranges through the EDL's clips, so the export
+/// only has to deal with ranges. Words cut from the edit are skipped.
+pub(crate) fn resolve_redaction(redaction: &ExportRedaction, tracks: &[ExportEDLTrack]) -> Result<ExportRedaction, String> {
+    let mut ranges = redaction.ranges.clone();
+
+    if !redaction.word_ids.is_empty() {
+        let source = match redaction.source_path.as_deref().or(tracks.first().map(|t| t.source_path.as_str())) {
+            Some(s) => s.to_string(),
+            None => return Err("Redaction by word needs a source path or at least one track".to_string()),
+        };
+        let wanted: HashSet<&str> = redaction.word_ids.iter().map(|s| s.as_str()).collect();
+        let found: Vec<&Word> = redaction.words.iter().filter(|w| wanted.contains(w.id.as_str())).collect();
+        if found.len() < wanted.len() {
+            return Err(format!(
+                "{} redacted word(s) not found in the transcript",
+                wanted.len() - found.len()
+            ));
+        }
+
+        for w in found {
+            let start = cutlist::source_to_timeline(tracks, &source, w.start);
+            let end = cutlist::source_to_timeline(tracks, &source, (w.end - 1e-6).max(w.start)).map(|t| t + 1e-6);
+            let (start, end) = match (start, end) {
+                (Some(s), Some(e)) if e > s => (s, e),
+                (Some(s), _) => (s, s + (w.end - w.start)),
+                (None, Some(e)) => ((e - (w.end - w.start)).max(0.0), e),
+                (None, None) => continue, // cut from the edit
+            };
+            ranges.push(RedactionRange { start, end, style: redaction.style });
+        }
+    }
+
+    ranges.retain(|r| r.end > r.start);
+    ranges.sort_by(|a, b| a.start.partial_cmp(&b.start).unwrap_or(std::cmp::Ordering::Equal));
+    Ok(ExportRedaction {
+        ranges,
+        word_ids: Vec::new(),
+        words: Vec::new(),
+        source_path: None,
+        style: redaction.style,
+        fade: redaction.fade.max(0.0),
+    })
+}
+
+/// Deterministic white noise in [-1, 1] for an output frame (same value for every chunk size)
+fn noise_sample(frame: u64) -> f32 {
+    let mut x = frame.wrapping_mul(0x9E37_79B9_7F4A_7C15) ^ 0x2545_F491_4F6C_DD1D;
+    x ^= x >> 33;
+    x = x.wrapping_mul(0xFF51_AFD7_ED55_8CCD);
+    x ^= x >> 33;
+    (x >> 40) as f32 / (1u64 << 23) as f32 - 1.0
+}
+
+/// Replace redacted ranges in a mixed chunk (`mix_buf` as filled by `mix_chunk`).
+/// Ranges must already be timeline ranges (see `resolve_redaction`).
+pub(crate) fn apply_redactions(
+    mix_buf: &mut [f32],
+    redaction: Option<&ExportRedaction>,
+    start_frame: usize,
+    timeline_start: f64,
+    output_rate: f64,
+    output_channels: usize,
+) {
+    let Some(redaction) = redaction else { return };
+    if output_channels == 0 {
+        return;
+    }
+    let frame_count = mix_buf.len() / output_channels;
+    let fade = redaction.fade;
+    let chunk_t0 = timeline_start + start_frame as f64 / output_rate;
+    let chunk_t1 = timeline_start + (start_frame + frame_count) as f64 / output_rate;
+
+    for range in &redaction.ranges {
+        let (from, to) = (range.start - fade, range.end + fade);
+        if to <= chunk_t0 || from >= chunk_t1 {
+            continue;
+        }
+        let first = (((from - chunk_t0) * output_rate).floor().max(0.0) as usize).min(frame_count);
+        let last = (((to - chunk_t0) * output_rate).ceil().max(0.0) as usize).min(frame_count);
+
+        for i in first..last {
+            let t = chunk_t0 + i as f64 / output_rate;
+            // 1 inside the range, linear ramps over `fade` on either side
+            let gain = if t < range.start {
+                if fade > 0.0 { 1.0 - (range.start - t) / fade } else { 0.0 }
+            } else if t > range.end {
+                if fade > 0.0 { 1.0 - (t - range.end) / fade } else { 0.0 }
+            } else {
+                1.0
+            }
+            .clamp(0.0, 1.0) as f32;
+            if gain <= 0.0 {
+                continue;
+            }
+
+            let replacement = match range.style {
+                RedactionStyle::Tone => TONE_LEVEL * (2.0 * std::f64::consts::PI * TONE_HZ * (t - range.start)).sin() as f32,
+                RedactionStyle::Silence => 0.0,
+                RedactionStyle::Noise => NOISE_LEVEL * noise_sample((start_frame + i) as u64),
+            };
+            for s in &mut mix_buf[i * output_channels..(i + 1) * output_channels] {
+                *s = *s * (1.0 - gain) + replacement * gain;
+            }
+        }
+    }
+}
+
+/// Find transcript words/phrases to redact (case and punctuation are ignored)
+#[tauri::command]
+pub async fn find_redaction_matches(
+    words: Vec<Word>,
+    terms: Vec<String>,
+) -> Result<Vec<RedactionMatch>, String> {
+    let options = FillerOptions { fillers: terms, min_confidence: 0.0 };
+    let matches: Vec<RedactionMatch> = fillers::find_fillers(&words, &options)
+        .into_iter()
+        .map(|m| RedactionMatch { word_ids: m.word_ids, text: m.text, start: m.start, end: m.end })
+        .collect();
+    log::info!("Redaction search: {} matches in {} words", matches.len(), words.len());
+    Ok(matches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::transcribe::test_word;
+
+    fn ranges(list: &[(f64, f64, RedactionStyle)]) -> ExportRedaction {
+        ExportRedaction {
+            ranges: list.iter().map(|&(start, end, style)| RedactionRange { start, end, style }).collect(),
+            fade: 0.01,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_apply_silence_with_fades() {
+        // 1 kHz mono output, constant 0.5 signal, silence 0.1–0.2s with 10ms fades
+        let mut buf = vec![0.5f32; 300];
+        let redaction = ranges(&[(0.1, 0.2, RedactionStyle::Silence)]);
+        apply_redactions(&mut buf, Some(&redaction), 0, 0.0, 1000.0, 1);
+        assert_eq!(buf[50], 0.5);
+        assert!((buf[95] - 0.25).abs() < 1e-6, "mid fade-in {}", buf[95]);
+        assert_eq!(buf[150], 0.0);
+        assert!((buf[205] - 0.25).abs() < 1e-6, "mid fade-out {}", buf[205]);
+        assert_eq!(buf[250], 0.5);
+    }
+
+    #[test]
+    fn test_apply_is_chunk_independent() {
+        let redaction = ranges(&[(0.05, 0.15, RedactionStyle::Noise), (0.2, 0.25, RedactionStyle::Tone)]);
+        let mut whole = vec![0.1f32; 600];
+        apply_redactions(&mut whole, Some(&redaction), 0, 0.0, 2000.0, 2);
+
+        let mut chunked = Vec::new();
+        for c in 0..3 {
+            let mut part = vec![0.1f32; 200];
+            apply_redactions(&mut part, Some(&redaction), c * 100, 0.0, 2000.0, 2);
+            chunked.extend(part);
+        }
+        assert_eq!(whole, chunked);
+        assert!(whole[2 * 220..2 * 240].iter().all(|s| s.abs() <= TONE_LEVEL + 1e-6));
+    }
+
+    #[test]
+    fn test_resolve_words_through_clips() {
+        // Keep 0–1s and 2–3s of the source
+        let clips = cutlist::ranges_to_clips(
+            "a.wav",
+            &[cutlist::KeepRange { start: 0.0, end: 1.0 }, cutlist::KeepRange { start: 2.0, end: 3.0 }],
+            0.0,
+            3.0,
+        );
+        let redaction = ExportRedaction {
+            word_ids: vec!["late".to_string(), "gone".to_string()],
+            words: vec![
+                test_word("early", "early", 0.2, 0.4),
+                test_word("gone", "gone", 1.2, 1.5),
+                test_word("late", "late", 2.5, 2.8),
+            ],
+            style: RedactionStyle::Noise,
+            fade: 0.005,
+            ..Default::default()
+        };
+        let resolved = resolve_redaction(&redaction, &clips).unwrap();
+        // "gone" was cut from the edit; "late" moves back by 1s
+        assert_eq!(resolved.ranges.len(), 1);
+        assert!((resolved.ranges[0].start - 1.5).abs() < 1e-6 && (resolved.ranges[0].end - 1.8).abs() < 1e-6);
+        assert_eq!(resolved.ranges[0].style, RedactionStyle::Noise);
+
+        let missing = ExportRedaction { word_ids: vec!["nope".to_string()], ..redaction };
+        assert!(resolve_redaction(&missing, &clips).is_err());
+    }
+}
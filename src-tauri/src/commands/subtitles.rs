@@ -0,0 +1,418 @@
+//! Caption export: group transcript words into cues and write SRT / WebVTT
+
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::path::Path;
+
+use super::cutlist;
+use super::export::ExportEDLTrack;
+use super::metadata::TranscriptionMetadata;
+use super::transcribe::Word;
+
+/// Cues shorter than this are held on screen longer when the next cue allows it
+const MIN_CUE_SECS: f64 = 0.8;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SubtitleOptions {
+    /// "srt" or "vtt" (None = from the output file extension)
+    pub format: Option<String>,
+    pub max_chars_per_line: usize,
+    pub max_lines: usize,
+    /// Longest time a cue stays on screen, in seconds
+    pub max_cue_duration: f64,
+    /// Start a new cue after a pause at least this long, in seconds
+    pub pause_gap: f64,
+    /// Word-level timing: inline timestamps in WebVTT, one cue per highlighted word in SRT
+    pub karaoke: bool,
+}
+
+impl Default for SubtitleOptions {
+    fn default() -> Self {
+        Self {
+            format: None,
+            max_chars_per_line: 42,
+            max_lines: 2,
+            max_cue_duration: 6.0,
+            pause_gap: 0.7,
+            karaoke: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubtitleExportResult {
+    pub output_path: String,
+    pub format: String,
+    pub cue_count: usize,
+    /// Words dropped because they fall in material cut from the edit
+    pub skipped_words: usize,
+}
+
+/// A word placed on the output timeline
+#[derive(Debug, Clone, PartialEq)]
+struct TimedWord {
+    text: String,
+    start: f64,
+    end: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Cue {
+    start: f64,
+    end: f64,
+    lines: Vec<String>,
+    /// Number of `words` on each of `lines`
+    line_words: Vec<usize>,
+    words: Vec<TimedWord>,
+}
+
+/// Apply the metadata's global offset and per-word adjustments, then map the words
+/// onto the edit's timeline (dropping words that were cut). Returns words in timeline order.
+fn place_words(
+    words: &[Word],
+    metadata: Option<&TranscriptionMetadata>,
+    edl: Option<(&[ExportEDLTrack], &str)>,
+) -> Vec<TimedWord> {
+    let word_shift = metadata.map(|m| m.word_shift());
+
+    let mut placed: Vec<TimedWord> = words
+        .iter()
+        .filter_map(|w| {
+            let shift = word_shift.as_ref().map_or(0.0, |shift| shift(w.id.as_str()));
+            let (start, end) = ((w.start + shift).max(0.0), (w.end + shift).max(0.0));
+            let (start, end) = match edl {
+                Some((clips, source)) => {
+                    let tl_start = cutlist::source_to_timeline(clips, source, start)?;
+                    // A word straddling a cut keeps its length up to where it was mapped
+                    let tl_end = cutlist::source_to_timeline(clips, source, (end - 1e-6).max(start))
+                        .map(|t| t + 1e-6)
+                        .filter(|&t| t >= tl_start)
+                        .unwrap_or(tl_start + (end - start));
+                    (tl_start, tl_end)
+                }
+                None => (start, end),
+            };
+            Some(TimedWord { text: w.text.trim().to_string(), start, end })
+        })
+        .filter(|w| !w.text.is_empty())
+        .collect();
+    placed.sort_by(|a, b| a.start.partial_cmp(&b.start).unwrap_or(std::cmp::Ordering::Equal));
+    placed
+}
+
+/// Greedy line wrap; a word longer than a line gets a line of its own.
+/// Returns each line with the number of words on it (a word's text may contain spaces).
+fn wrap_lines(words: &[TimedWord], max_chars: usize) -> Vec<(String, usize)> {
+    let mut lines: Vec<(String, usize)> = Vec::new();
+    let mut current = String::new();
+    let mut count = 0;
+    for w in words {
+        if count > 0 && current.chars().count() + 1 + w.text.chars().count() > max_chars {
+            lines.push((std::mem::take(&mut current), count));
+            count = 0;
+        }
+        if count > 0 {
+            current.push(' ');
+        }
+        current.push_str(&w.text);
+        count += 1;
+    }
+    if count > 0 {
+        lines.push((current, count));
+    }
+    lines
+}
+
+fn ends_sentence(text: &str) -> bool {
+    text.ends_with(['.', '?', '!'])
+}
+
+/// Group words into cues within the line, duration and pause limits
+fn build_cues(words: &[TimedWord], opts: &SubtitleOptions) -> Vec<Cue> {
+    let max_chars = opts.max_chars_per_line.max(1);
+    let max_lines = opts.max_lines.max(1);
+    let mut groups: Vec<Vec<TimedWord>> = Vec::new();
+    let mut current: Vec<TimedWord> = Vec::new();
+
+    for w in words {
+        if let (Some(first), Some(prev)) = (current.first(), current.last()) {
+            let pause = w.start - prev.end >= opts.pause_gap;
+            let too_long = w.end - first.start > opts.max_cue_duration;
+            let mut candidate = current.clone();
+            candidate.push(w.clone());
+            let too_many_lines = wrap_lines(&candidate, max_chars).len() > max_lines;
+            if pause || too_long || too_many_lines || ends_sentence(&prev.text) {
+                groups.push(std::mem::take(&mut current));
+            }
+        }
+        current.push(w.clone());
+    }
+    if !current.is_empty() {
+        groups.push(current);
+    }
+
+    let mut cues: Vec<Cue> = groups
+        .into_iter()
+        .map(|group| {
+            let (lines, line_words) = wrap_lines(&group, max_chars).into_iter().unzip();
+            Cue {
+                start: group[0].start,
+                end: group.iter().map(|w| w.end).fold(group[0].start, f64::max),
+                lines,
+                line_words,
+                words: group,
+            }
+        })
+        .collect();
+
+    // Hold very short cues a little longer, without running into the next one
+    for i in 0..cues.len() {
+        let limit = cues.get(i + 1).map(|c| c.start).unwrap_or(f64::INFINITY);
+        let cue = &mut cues[i];
+        if cue.end - cue.start < MIN_CUE_SECS {
+            cue.end = cue.end.max((cue.start + MIN_CUE_SECS).min(limit));
+        }
+    }
+    cues
+}
+
+/// HH:MM:SS,mmm (SRT) or HH:MM:SS.mmm (WebVTT)
+pub(crate) fn format_timestamp(seconds: f64, separator: char) -> String {
+    let total_ms = (seconds.max(0.0) * 1000.0).round() as u64;
+    let (h, m, s, ms) = (total_ms / 3_600_000, total_ms / 60_000 % 60, total_ms / 1000 % 60, total_ms % 1000);
+    format!("{:02}:{:02}:{:02}{}{:03}", h, m, s, separator, ms)
+}
+
+fn escape_vtt(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// Cue lines with `highlight` (a word index) underlined, for SRT karaoke
+fn highlighted_lines(cue: &Cue, highlight: usize) -> Vec<String> {
+    let marked: Vec<TimedWord> = cue
+        .words
+        .iter()
+        .enumerate()
+        .map(|(i, w)| TimedWord {
+            text: if i == highlight { format!("<u>{}</u>", w.text) } else { w.text.clone() },
+            ..w.clone()
+        })
+        .collect();
+    // Keep the plain text's wrapping so lines don't jump as the highlight moves
+    let mut lines = Vec::with_capacity(cue.line_words.len());
+    let mut next = 0;
+    for &count in &cue.line_words {
+        lines.push(marked[next..next + count].iter().map(|w| w.text.as_str()).collect::<Vec<_>>().join(" "));
+        next += count;
+    }
+    lines
+}
+
+fn render_srt(cues: &[Cue], opts: &SubtitleOptions) -> String {
+    let mut out = String::new();
+    let mut index = 1;
+    for cue in cues {
+        if opts.karaoke && cue.words.len() > 1 {
+            for (i, w) in cue.words.iter().enumerate() {
+                let end = cue.words.get(i + 1).map(|n| n.start).unwrap_or(cue.end);
+                let _ = writeln!(out, "{}", index);
+                let _ = writeln!(out, "{} --> {}", format_timestamp(w.start.max(cue.start), ','), format_timestamp(end, ','));
+                let _ = writeln!(out, "{}\n", highlighted_lines(cue, i).join("\n"));
+                index += 1;
+            }
+        } else {
+            let _ = writeln!(out, "{}", index);
+            let _ = writeln!(out, "{} --> {}", format_timestamp(cue.start, ','), format_timestamp(cue.end, ','));
+            let _ = writeln!(out, "{}\n", cue.lines.join("\n"));
+            index += 1;
+        }
+    }
+    out
+}
+
+fn render_vtt(cues: &[Cue], opts: &SubtitleOptions) -> String {
+    let mut out = String::from("WEBVTT\n\n");
+    for cue in cues {
+        let _ = writeln!(out, "{} --> {}", format_timestamp(cue.start, '.'), format_timestamp(cue.end, '.'));
+        if opts.karaoke {
+            // Inline timestamps before every word after the first
+            let mut next = 0;
+            let mut lines = Vec::with_capacity(cue.line_words.len());
+            for &count in &cue.line_words {
+                let text = cue.words[next..next + count]
+                    .iter()
+                    .enumerate()
+                    .map(|(i, w)| {
+                        if next + i == 0 {
+                            escape_vtt(&w.text)
+                        } else {
+                            format!("<{}>{}", format_timestamp(w.start, '.'), escape_vtt(&w.text))
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                lines.push(text);
+                next += count;
+            }
+            let _ = writeln!(out, "{}\n", lines.join("\n"));
+        } else {
+            let _ = writeln!(out, "{}\n", cue.lines.iter().map(|l| escape_vtt(l)).collect::<Vec<_>>().join("\n"));
+        }
+    }
+    out
+}
+
+/// Write captions for `words` to `output_path` as SRT or WebVTT.
+///
+/// `metadata` supplies the global offset and per-word timing adjustments. With
+/// `clips` (the exported edit), words are moved to the edit's timeline and words in
+/// cut material are dropped; `source_path` names the clips' source for the words
+/// (default: the metadata's audio path, or the clips' only source).
+#[tauri::command]
+pub async fn export_subtitles(
+    words: Vec<Word>,
+    output_path: String,
+    options: Option<SubtitleOptions>,
+    metadata: Option<TranscriptionMetadata>,
+    clips: Option<Vec<ExportEDLTrack>>,
+    source_path: Option<String>,
+) -> Result<SubtitleExportResult, String> {
+    let opts = options.unwrap_or_default();
+    let format = match opts.format.clone() {
+        Some(f) => f.to_lowercase(),
+        None => Path::new(&output_path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase())
+            .unwrap_or_else(|| "srt".to_string()),
+    };
+    if format != "srt" && format != "vtt" {
+        return Err(format!("Unsupported subtitle format '{}': expected \"srt\" or \"vtt\"", format));
+    }
+
+    let source = match (&clips, source_path) {
+        (None, _) => None,
+        (Some(_), Some(path)) => Some(path),
+        (Some(clips), None) => {
+            let from_metadata = metadata.as_ref().map(|m| m.audio_path.clone());
+            let mut sources: Vec<&str> = clips.iter().map(|c| c.source_path.as_str()).collect();
+            sources.sort_unstable();
+            sources.dedup();
+            match (from_metadata, sources.as_slice()) {
+                (Some(path), _) => Some(path),
+                (None, [only]) => Some(only.to_string()),
+                _ => return Err("Cut list has several sources; pass sourcePath for the transcript's audio".to_string()),
+            }
+        }
+    };
+    let edl = clips.as_deref().zip(source.as_deref());
+
+    let placed = place_words(&words, metadata.as_ref(), edl);
+    let cues = build_cues(&placed, &opts);
+    let content = if format == "vtt" { render_vtt(&cues, &opts) } else { render_srt(&cues, &opts) };
+
+    std::fs::write(&output_path, content)
+        .map_err(|e| format!("Failed to write subtitles: {}", e))?;
+
+    log::info!("Exported {} {} cues to {}", cues.len(), format, output_path);
+    Ok(SubtitleExportResult {
+        output_path,
+        format,
+        cue_count: cues.len(),
+        skipped_words: words.len() - placed.len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::metadata::WordTimingAdjustment;
+    use crate::commands::transcribe::test_word;
+
+    fn timed(words: &[(&str, f64, f64)]) -> Vec<TimedWord> {
+        words.iter().map(|&(t, s, e)| TimedWord { text: t.to_string(), start: s, end: e }).collect()
+    }
+
+    #[test]
+    fn test_cues_split_on_lines_and_pauses() {
+        let words = timed(&[
+            ("one", 0.0, 0.3), ("two", 0.3, 0.6), ("three", 0.6, 0.9),
+            ("four", 2.0, 2.3), ("five", 2.3, 2.6),
+        ]);
+        let opts = SubtitleOptions { max_chars_per_line: 8, max_lines: 1, ..Default::default() };
+        let cues = build_cues(&words, &opts);
+        let lines: Vec<Vec<String>> = cues.iter().map(|c| c.lines.clone()).collect();
+        // "one two" fills a line, the pause before "four" starts a new cue
+        assert_eq!(lines, vec![vec!["one two"], vec!["three"], vec!["four"], vec!["five"]]);
+
+        let opts = SubtitleOptions { max_chars_per_line: 8, max_lines: 2, ..Default::default() };
+        let cues = build_cues(&words, &opts);
+        assert_eq!(cues.len(), 2);
+        assert_eq!(cues[0].lines, vec!["one two", "three"]);
+        assert_eq!(cues[1].lines, vec!["four", "five"]);
+    }
+
+    #[test]
+    fn test_render_srt_and_vtt() {
+        let words = timed(&[("Hello", 1.0, 1.4), ("<world>", 1.5, 2.0)]);
+        let cues = build_cues(&words, &SubtitleOptions::default());
+        assert_eq!(
+            render_srt(&cues, &SubtitleOptions::default()),
+            "1\n00:00:01,000 --> 00:00:02,000\nHello <world>\n\n"
+        );
+
+        let karaoke = SubtitleOptions { karaoke: true, ..Default::default() };
+        assert_eq!(
+            render_vtt(&cues, &karaoke),
+            "WEBVTT\n\n00:00:01.000 --> 00:00:02.000\nHello <00:00:01.500>&lt;world&gt;\n\n"
+        );
+        let srt = render_srt(&cues, &karaoke);
+        assert!(srt.starts_with("1\n00:00:01,000 --> 00:00:01,500\n<u>Hello</u> <world>\n\n2\n00:00:01,500 --> 00:00:02,000\n"));
+    }
+
+    #[test]
+    fn test_karaoke_with_spaces_inside_words() {
+        let words = timed(&[("New York", 0.0, 0.5), ("is", 0.5, 0.7), ("big", 0.7, 1.0)]);
+        let karaoke = SubtitleOptions { karaoke: true, max_chars_per_line: 11, ..Default::default() };
+        let cues = build_cues(&words, &karaoke);
+        assert_eq!(cues[0].lines, vec!["New York is", "big"]);
+        assert_eq!(cues[0].line_words, vec![2, 1]);
+
+        let srt = render_srt(&cues, &karaoke);
+        assert!(srt.contains("<u>New York</u> is\nbig"));
+        assert!(srt.contains("New York is\n<u>big</u>"));
+        let vtt = render_vtt(&cues, &karaoke);
+        assert!(vtt.contains("New York <00:00:00.500>is\n<00:00:00.700>big"));
+    }
+
+    #[test]
+    fn test_place_words_applies_offsets_and_cuts() {
+        let words = vec![test_word("a", "keep", 0.5, 0.9), test_word("b", "cut", 2.2, 2.6), test_word("c", "later", 4.2, 4.6)];
+        let metadata = TranscriptionMetadata {
+            audio_path: "a.wav".to_string(),
+            global_offset_ms: 100.0,
+            word_adjustments: vec![WordTimingAdjustment { word_id: "c".to_string(), offset_ms: -200.0 }],
+            ..Default::default()
+        };
+        // Keep 0–2s and 4–6s: "cut" is removed, "later" moves back by 2s
+        let clips = cutlist::ranges_to_clips(
+            "a.wav",
+            &[cutlist::KeepRange { start: 0.0, end: 2.0 }, cutlist::KeepRange { start: 4.0, end: 6.0 }],
+            0.0,
+            6.0,
+        );
+        let placed = place_words(&words, Some(&metadata), Some((&clips, "a.wav")));
+        assert_eq!(placed.len(), 2);
+        assert!((placed[0].start - 0.6).abs() < 1e-6 && (placed[0].end - 1.0).abs() < 1e-6);
+        assert_eq!(placed[1].text, "later");
+        assert!((placed[1].start - 2.1).abs() < 1e-6 && (placed[1].end - 2.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(3725.0456, ','), "01:02:05,046");
+        assert_eq!(format_timestamp(-1.0, '.'), "00:00:00.000");
+    }
+}
@@ -0,0 +1,215 @@
+//! Text-based editing: compile word deletions and reorderings into an EDL cut list
+
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use super::chunking::{self, SampleSource, Stream16k};
+use super::cutlist::{self, KeepRange};
+use super::export::ExportEDLTrack;
+use super::transcribe::Word;
+
+/// Default crossfade at text-edit cuts in seconds
+const DEFAULT_TEXT_EDIT_CROSSFADE: f64 = 0.01;
+/// Energy frame for cut snapping (10ms at 16 kHz)
+const ENERGY_FRAME: usize = 160;
+/// How far a cut may move into the neighbouring words' edges, in seconds
+/// (word timings are rarely exact at the boundaries)
+const SNAP_MARGIN: f64 = 0.03;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextEditCutList {
+    pub clips: Vec<ExportEDLTrack>,
+    pub original_duration: f64,
+    pub output_duration: f64,
+    pub kept_words: usize,
+}
+
+/// Split a playback sequence (indices into source-ordered words) into runs of
+/// source-consecutive words, each played as one clip. Returns (first, last) indices.
+fn plan_runs(sequence: &[usize]) -> Vec<(usize, usize)> {
+    let mut runs: Vec<(usize, usize)> = Vec::new();
+    for &idx in sequence {
+        match runs.last_mut() {
+            Some((_, last)) if idx == *last + 1 => *last = idx,
+            _ => runs.push((idx, idx)),
+        }
+    }
+    runs
+}
+
+/// RMS per `ENERGY_FRAME` of a 16 kHz stream
+fn frame_energies<S: SampleSource>(source: &mut S) -> Result<Vec<f32>, String> {
+    let mut energies = Vec::new();
+    let mut buf: Vec<f32> = Vec::with_capacity(ENERGY_FRAME * 256);
+    loop {
+        let n = source.read(ENERGY_FRAME * 256 - buf.len(), &mut buf)?;
+        let full_frames = buf.len() / ENERGY_FRAME;
+        for frame in buf.chunks(ENERGY_FRAME).take(if n == 0 { usize::MAX } else { full_frames }) {
+            energies.push((frame.iter().map(|s| s * s).sum::<f32>() / frame.len() as f32).sqrt());
+        }
+        if n == 0 {
+            return Ok(energies);
+        }
+        buf.drain(..full_frames * ENERGY_FRAME);
+    }
+}
+
+/// Quietest point in [lo, hi] (centre of the lowest-energy frame; ties go to the
+/// frame nearest the middle of the range)
+fn snap_to_quiet(energies: &[f32], lo: f64, hi: f64) -> f64 {
+    let frame_secs = ENERGY_FRAME as f64 / chunking::SAMPLE_RATE as f64;
+    let first = (lo / frame_secs).floor().max(0.0) as usize;
+    let last = ((hi / frame_secs).ceil() as usize).min(energies.len());
+    let middle = (lo + hi) / 2.0;
+    (first..last)
+        .map(|f| (f, (f as f64 + 0.5) * frame_secs))
+        .filter(|&(_, t)| t >= lo && t <= hi)
+        .min_by(|&(a, ta), &(b, tb)| {
+            energies[a]
+                .partial_cmp(&energies[b])
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then((ta - middle).abs().partial_cmp(&(tb - middle).abs()).unwrap_or(std::cmp::Ordering::Equal))
+        })
+        .map(|(_, t)| t)
+        .unwrap_or(middle)
+}
+
+/// Cut point between source words `k` and `k + 1`: the quietest spot in the gap,
+/// allowed to reach slightly into either word
+fn gap_cut(words: &[Word], k: usize, energies: &[f32]) -> f64 {
+    let (a, b) = (&words[k], &words[k + 1]);
+    let lo = (a.end - SNAP_MARGIN).max(a.start);
+    let hi = (b.start + SNAP_MARGIN).min(b.end);
+    if lo >= hi {
+        return (a.end + b.start) / 2.0;
+    }
+    snap_to_quiet(energies, lo, hi)
+}
+
+/// Keep ranges for runs of source-ordered words, cut at quiet points between words.
+/// The first word keeps everything before it and the last word everything after it.
+fn runs_to_ranges(words: &[Word], runs: &[(usize, usize)], energies: &[f32], duration: f64) -> Vec<KeepRange> {
+    runs.iter()
+        .map(|&(first, last)| KeepRange {
+            start: if first == 0 { 0.0 } else { gap_cut(words, first - 1, energies) },
+            end: if last + 1 == words.len() { duration } else { gap_cut(words, last, energies) },
+        })
+        .filter(|r| r.end > r.start)
+        .collect()
+}
+
+/// Turn a text edit (deleted and/or reordered words) into EDL clips into `source_path`.
+///
+/// Each run of words that stays together becomes one clip; cuts snap to the quietest
+/// point between words and get a short crossfade. The clips can go straight to
+/// `export_edl` or `playback_set_tracks`. `word_order` lists word IDs in their new
+/// playback order (default: source order); `deleted_word_ids` are dropped either way.
+#[tauri::command]
+pub async fn compile_text_edit(
+    source_path: String,
+    mut words: Vec<Word>,
+    deleted_word_ids: Vec<String>,
+    word_order: Option<Vec<String>>,
+    crossfade: Option<f64>,
+) -> Result<TextEditCutList, String> {
+    if words.is_empty() {
+        return Err("No words to edit".to_string());
+    }
+    words.sort_by(|a, b| a.start.partial_cmp(&b.start).unwrap_or(std::cmp::Ordering::Equal));
+    let index_of: HashMap<&str, usize> = words.iter().enumerate().map(|(i, w)| (w.id.as_str(), i)).collect();
+
+    let deleted: HashSet<&str> = deleted_word_ids.iter().map(|s| s.as_str()).collect();
+    if let Some(unknown) = deleted.iter().find(|id| !index_of.contains_key(**id)) {
+        return Err(format!("Deleted word '{}' is not in the word list", unknown));
+    }
+    let sequence: Vec<usize> = match &word_order {
+        Some(order) => order
+            .iter()
+            .filter(|id| !deleted.contains(id.as_str()))
+            .map(|id| index_of.get(id.as_str()).copied().ok_or_else(|| format!("Word '{}' in the new order is not in the word list", id)))
+            .collect::<Result<_, _>>()?,
+        None => (0..words.len()).filter(|&i| !deleted.contains(words[i].id.as_str())).collect(),
+    };
+
+    let meta = super::audio::get_audio_metadata(source_path.clone()).await?;
+    let path = source_path.clone();
+    let energies = tokio::task::spawn_blocking(move || {
+        let mut stream = Stream16k::open(Path::new(&path))?;
+        frame_energies(&mut stream)
+    })
+    .await
+    .map_err(|e| format!("Energy analysis task failed: {}", e))??;
+
+    let total_duration = if meta.duration > 0.0 {
+        meta.duration
+    } else {
+        (energies.len() * ENERGY_FRAME) as f64 / chunking::SAMPLE_RATE as f64
+    };
+
+    let runs = plan_runs(&sequence);
+    let ranges = runs_to_ranges(&words, &runs, &energies, total_duration);
+    let clips = cutlist::ranges_to_clips(
+        &source_path,
+        &ranges,
+        crossfade.unwrap_or(DEFAULT_TEXT_EDIT_CROSSFADE),
+        total_duration,
+    );
+    let output_duration = cutlist::clips_duration(&clips);
+
+    log::info!(
+        "Text edit: {} of {} words kept in {} clips, {:.1}s -> {:.1}s",
+        sequence.len(), words.len(), clips.len(), total_duration, output_duration
+    );
+
+    Ok(TextEditCutList {
+        clips,
+        original_duration: total_duration,
+        output_duration,
+        kept_words: sequence.len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::transcribe::test_word;
+
+    #[test]
+    fn test_plan_runs_groups_consecutive_words() {
+        // Delete word 2, move words 4-5 to the front
+        assert_eq!(plan_runs(&[4, 5, 0, 1, 3]), vec![(4, 5), (0, 1), (3, 3)]);
+        assert_eq!(plan_runs(&[0, 1, 2]), vec![(0, 2)]);
+        assert!(plan_runs(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_frame_energies_streams_all_frames() {
+        let samples: Vec<f32> = (0..1000).map(|i| if i < 480 { 0.5 } else { 0.0 }).collect();
+        let energies = frame_energies(&mut chunking::SliceSource::new(&samples)).unwrap();
+        // 6 full frames plus a partial one
+        assert_eq!(energies.len(), 7);
+        assert!((energies[0] - 0.5).abs() < 1e-6);
+        assert_eq!(energies[5], 0.0);
+    }
+
+    #[test]
+    fn test_cuts_snap_to_quiet_gap() {
+        // 10ms frames: loud everywhere except a quiet dip at 1.11–1.13s, just inside "b"
+        let mut energies = vec![0.3f32; 300];
+        for e in &mut energies[111..113] {
+            *e = 0.01;
+        }
+        let words = vec![test_word("a", "a", 0.2, 1.0), test_word("b", "b", 1.1, 1.9), test_word("c", "c", 2.0, 2.8)];
+        // Delete "a": the cut before "b" may reach 30ms into it and lands in the dip
+        let ranges = runs_to_ranges(&words, &[(1, 2)], &energies, 3.0);
+        assert_eq!(ranges.len(), 1);
+        assert!((ranges[0].start - 1.115).abs() < 1e-6, "cut at {}", ranges[0].start);
+        assert_eq!(ranges[0].end, 3.0);
+
+        // Equal energy across the gap: cut in the middle of it
+        let cut = gap_cut(&words, 1, &[0.1f32; 300]);
+        assert!((cut - 1.95).abs() < 0.006, "cut at {}", cut);
+    }
+}
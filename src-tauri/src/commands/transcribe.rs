@@ -1,16 +1,19 @@
 use serde::{Deserialize, Serialize};
-use std::fs::File;
-use std::io::Write;
-use std::path::Path;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::Instant;
-use symphonia::core::audio::SampleBuffer;
-use symphonia::core::codecs::DecoderOptions;
-use symphonia::core::formats::FormatOptions;
-use symphonia::core::io::MediaSourceStream;
-use symphonia::core::meta::MetadataOptions;
-use symphonia::core::probe::Hint;
-use tauri::{AppHandle, Manager};
-use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+use tauri::{AppHandle, Emitter, Manager};
+use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters, WhisperState};
+
+use super::alignment;
+use super::engine::{
+    EngineCapabilities, EngineModel, ModelLocations, ProgressReporter, TranscriptionEngine, TranscriptionJob,
+};
+use super::chunking::{self, AudioWindow, SampleSource, Stream16k, WindowConfig, Windower};
+use super::metadata;
+use crate::services::{model_download, path_service};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -22,6 +25,12 @@ pub struct Word {
     pub confidence: f64,
 }
 
+/// Word with confidence 0.9, for tests
+#[cfg(test)]
+pub(crate) fn test_word(id: &str, text: &str, start: f64, end: f64) -> Word {
+    Word { id: id.to_string(), text: text.to_string(), start, end, confidence: 0.9 }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TranscriptionMetrics {
@@ -36,15 +45,42 @@ pub struct TranscriptionMetrics {
     pub real_time_factor: f64,
 }
 
+/// A decoded Whisper segment (roughly a phrase), with its silence likelihood
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptionSegment {
+    pub start: f64,
+    pub end: f64,
+    pub text: String,
+    /// Probability that the segment is not speech (high values often mean hallucinated text)
+    pub no_speech_prob: f32,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TranscriptionResult {
     pub words: Vec<Word>,
     pub text: String,
     pub language: String,
+    /// Detection confidence when the language was auto-detected
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language_probability: Option<f32>,
+    /// "transcribe" (words in `language`) or "translate" (English words)
+    #[serde(default = "default_task")]
+    pub task: String,
+    /// Segment timings and no-speech probabilities (Whisper only)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub segments: Vec<TranscriptionSegment>,
     pub metrics: Option<TranscriptionMetrics>,
 }
 
+pub(crate) const TASK_TRANSCRIBE: &str = "transcribe";
+pub(crate) const TASK_TRANSLATE: &str = "translate";
+
+fn default_task() -> String {
+    TASK_TRANSCRIBE.to_string()
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ModelInfo {
@@ -102,65 +138,12 @@ fn find_model_path_internal(
 ) -> Result<std::path::PathBuf, String> {
     let model_files = get_model_filenames(model_name);
 
-    // Check custom path first if provided
-    if let Some(custom) = custom_path {
-        if !custom.is_empty() {
-            let custom_dir = Path::new(custom);
-            for model_file in &model_files {
-                let custom_model = custom_dir.join(model_file);
-                if custom_model.exists() {
-                    log::info!("Found model at custom path: {:?}", custom_model);
-                    return Ok(custom_model);
-                }
-            }
-        }
-    }
-
-    // Check bundled resources if available (production builds)
-    if let Some(res_dir) = resource_dir {
-        for model_file in &model_files {
-            let bundled = res_dir.join("models").join(model_file);
-            if bundled.exists() {
-                log::info!("Found bundled model: {:?}", bundled);
-                return Ok(bundled);
-            }
-        }
-    }
-
-    // Check adjacent to executable (Flatpak, portable installs)
-    if let Ok(exe) = std::env::current_exe() {
-        if let Some(exe_dir) = exe.parent() {
-            for model_file in &model_files {
-                let exe_model = exe_dir.join("models").join(model_file);
-                if exe_model.exists() {
-                    log::info!("Found model next to executable: {:?}", exe_model);
-                    return Ok(exe_model);
-                }
-            }
-        }
-    }
-
-    // Dev mode fallback: check src-tauri/resources/models via CARGO_MANIFEST_DIR
-    let dev_models_dir = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
-        .join("resources")
-        .join("models");
-    if dev_models_dir.exists() {
-        for model_file in &model_files {
-            let dev_model = dev_models_dir.join(model_file);
-            if dev_model.exists() {
-                log::info!("Found model at dev path: {:?}", dev_model);
-                return Ok(dev_model);
-            }
-        }
-    }
-
-    // Check app data directory
-    if let Ok(models_dir) = get_models_dir() {
+    for (kind, dir) in path_service::model_search_dirs(custom_path, resource_dir) {
         for model_file in &model_files {
-            let app_model = models_dir.join(model_file);
-            if app_model.exists() {
-                log::info!("Found model at app data: {:?}", app_model);
-                return Ok(app_model);
+            let path = dir.join(model_file);
+            if path.exists() {
+                log::info!("Found model at {}: {:?}", kind, path);
+                return Ok(path);
             }
         }
     }
@@ -292,208 +275,461 @@ fn find_any_model(custom_path: Option<&str>) -> Result<std::path::PathBuf, Strin
         .or_else(|_| find_model_path("large", custom_path))
 }
 
-/// Load audio and convert to 16kHz mono f32 samples (required by whisper/moonshine)
+/// Load audio and convert to 16kHz mono f32 samples (required by whisper/moonshine).
+/// Whole-file loads are capped at 6 hours; longer files go through `chunking::Windower`.
 fn load_audio_16khz(path: &Path) -> Result<Vec<f32>, String> {
-    let file = File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
-    let mss = MediaSourceStream::new(Box::new(file), Default::default());
-
-    let mut hint = Hint::new();
-    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
-        hint.with_extension(ext);
-    }
-
-    let format_opts = FormatOptions::default();
-    let metadata_opts = MetadataOptions::default();
-    let decoder_opts = DecoderOptions::default();
-
-    let probed = symphonia::default::get_probe()
-        .format(&hint, mss, &format_opts, &metadata_opts)
-        .map_err(|e| format!("Failed to probe format: {}", e))?;
+    let mut stream = Stream16k::open(path)?;
 
-    let mut format = probed.format;
-
-    let track = format
-        .tracks()
-        .iter()
-        .find(|t| t.codec_params.codec != symphonia::core::codecs::CODEC_TYPE_NULL)
-        .ok_or("No audio tracks found")?;
-
-    let track_id = track.id;
-    let sample_rate = track.codec_params.sample_rate.unwrap_or(44100) as f64;
-    let channels = track.codec_params.channels.map(|c| c.count()).unwrap_or(2);
-
-    // Size guard: estimate 16kHz mono samples and reject files >6 hours
-    let max_16k_samples = 16000 * 3600 * 6; // 6 hours at 16kHz
-    if let Some(n_frames) = track.codec_params.n_frames {
-        let estimated_16k = (n_frames as f64 / sample_rate * 16000.0) as usize;
-        if estimated_16k > max_16k_samples {
-            let hours = n_frames as f64 / sample_rate / 3600.0;
+    // Size guard: reject files >6 hours
+    let max_secs = 3600.0 * 6.0;
+    let estimated_16k = match stream.duration_secs() {
+        Some(secs) if secs > max_secs => {
             return Err(format!(
                 "Audio file is too long for transcription ({:.1} hours). Maximum supported duration is 6 hours.",
-                hours
+                secs / 3600.0
             ));
         }
-    }
+        Some(secs) => (secs * chunking::SAMPLE_RATE as f64) as usize,
+        None => 0,
+    };
 
-    let mut decoder = symphonia::default::get_codecs()
-        .make(&track.codec_params, &decoder_opts)
-        .map_err(|e| format!("Failed to create decoder: {}", e))?;
+    let mut samples: Vec<f32> = Vec::with_capacity(estimated_16k + 1);
+    while stream.read(1 << 16, &mut samples)? > 0 {}
+    Ok(samples)
+}
 
-    // Collect all mono samples with capacity hint (at original sample rate, mono)
-    let estimated_mono = track.codec_params.n_frames
-        .map(|n| n as usize / channels.max(1))
-        .unwrap_or(0);
-    let mut mono_samples: Vec<f32> = Vec::with_capacity(estimated_mono);
+/// Public wrapper for load_audio_16khz (used by moonshine module)
+pub fn load_audio_16khz_pub(path: &Path) -> Result<Vec<f32>, String> {
+    load_audio_16khz(path)
+}
 
-    loop {
-        let packet = match format.next_packet() {
-            Ok(packet) => packet,
-            Err(symphonia::core::errors::Error::IoError(e))
-                if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
-            Err(_) => break,
-        };
+// ── Whisper context cache ──
 
-        if packet.track_id() != track_id {
-            continue;
-        }
+/// A loaded model kept between transcriptions
+struct CachedContext {
+    ctx: Arc<WhisperContext>,
+    size_bytes: u64,
+    last_used: Instant,
+}
 
-        let decoded = match decoder.decode(&packet) {
-            Ok(decoded) => decoded,
-            Err(_) => continue,
-        };
+/// Loaded Whisper contexts keyed by model path (avoids reloading the model on every run).
+static CONTEXT_CACHE: std::sync::OnceLock<Mutex<HashMap<String, CachedContext>>> = std::sync::OnceLock::new();
 
-        let spec = *decoded.spec();
-        let duration = decoded.capacity() as u64;
+/// Never keep more than this many models loaded
+const MAX_CACHED_CONTEXTS: usize = 2;
+/// Memory to leave free for audio buffers, the UI and the OS when loading a model
+const MEMORY_HEADROOM_BYTES: u64 = 1024 * 1024 * 1024;
+/// Cache budget when free memory can't be read (non-Linux)
+const FALLBACK_CACHE_BYTES: u64 = 4 * 1024 * 1024 * 1024;
 
-        let mut sample_buf = SampleBuffer::<f32>::new(duration, spec);
-        sample_buf.copy_interleaved_ref(decoded);
+fn context_cache() -> &'static Mutex<HashMap<String, CachedContext>> {
+    CONTEXT_CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
 
-        let samples = sample_buf.samples();
+/// `MemAvailable` from /proc/meminfo contents, in bytes
+fn parse_mem_available(meminfo: &str) -> Option<u64> {
+    meminfo
+        .lines()
+        .find(|l| l.starts_with("MemAvailable:"))
+        .and_then(|l| l.split_whitespace().nth(1))
+        .and_then(|kb| kb.parse::<u64>().ok())
+        .map(|kb| kb * 1024)
+}
+
+fn available_memory_bytes() -> Option<u64> {
+    std::fs::read_to_string("/proc/meminfo").ok().and_then(|m| parse_mem_available(&m))
+}
 
-        // Mix to mono
-        for chunk in samples.chunks(channels) {
-            let mono = chunk.iter().sum::<f32>() / channels as f32;
-            mono_samples.push(mono);
+/// Pick least-recently-used entries to drop so a model of `needed` bytes fits.
+/// `entries` is (key, size, last_used); `available` is free system memory, if known.
+fn plan_evictions(entries: &[(String, u64, Instant)], needed: u64, available: Option<u64>) -> Vec<String> {
+    let mut by_age: Vec<&(String, u64, Instant)> = entries.iter().collect();
+    by_age.sort_by_key(|(_, _, used)| *used);
+
+    let cached: u64 = entries.iter().map(|(_, size, _)| size).sum();
+    let mut freed = 0u64;
+    let mut evict = Vec::new();
+    for (key, size, _) in by_age {
+        let fits = match available {
+            Some(avail) => avail + freed >= needed + MEMORY_HEADROOM_BYTES,
+            None => cached - freed + needed <= FALLBACK_CACHE_BYTES,
+        };
+        if fits && entries.len() - evict.len() < MAX_CACHED_CONTEXTS {
+            break;
         }
+        evict.push(key.clone());
+        freed += size;
     }
+    evict
+}
 
-    // Resample to 16kHz if needed
-    const TARGET_RATE: f64 = 16000.0;
-    if (sample_rate - TARGET_RATE).abs() < 1.0 {
-        // Already 16kHz
-        return Ok(mono_samples);
+/// Drop cached contexts (least recently used first) until a model of `needed` bytes fits
+fn evict_contexts(cache: &mut HashMap<String, CachedContext>, needed: u64) {
+    let entries: Vec<(String, u64, Instant)> = cache
+        .iter()
+        .map(|(k, e)| (k.clone(), e.size_bytes, e.last_used))
+        .collect();
+    for evicted in plan_evictions(&entries, needed, available_memory_bytes()) {
+        log::info!("Evicting cached whisper context: {}", evicted);
+        cache.remove(&evicted);
     }
+}
 
-    // Simple linear resampling (for better quality, use rubato)
-    let ratio = TARGET_RATE / sample_rate;
-    let new_len = (mono_samples.len() as f64 * ratio) as usize;
-    let mut resampled = Vec::with_capacity(new_len);
+/// Get a loaded context for `model_path`, loading it (and evicting others) if needed.
+/// Returns the context and whether it came from the cache.
+fn get_or_load_context(model_path: &Path) -> Result<(Arc<WhisperContext>, bool), String> {
+    let key = model_path.to_string_lossy().to_string();
+    let size_bytes = std::fs::metadata(model_path).map(|m| m.len()).unwrap_or(0);
+    {
+        let mut cache = context_cache().lock().expect("CONTEXT_CACHE mutex poisoned");
+        if let Some(entry) = cache.get_mut(&key) {
+            entry.last_used = Instant::now();
+            log::info!("Reusing cached whisper context: {}", key);
+            return Ok((entry.ctx.clone(), true));
+        }
+        evict_contexts(&mut cache, size_bytes);
+    }
 
-    for i in 0..new_len {
-        let src_pos = i as f64 / ratio;
-        let src_idx = src_pos as usize;
-        let frac = src_pos - src_idx as f64;
+    // Load outside the lock — large models take seconds
+    let ctx = WhisperContext::new_with_params(
+        model_path.to_str()
+            .ok_or_else(|| format!("Model path contains invalid UTF-8: {:?}", model_path))?,
+        WhisperContextParameters::default(),
+    )
+    .map_err(|e| format!("Failed to load whisper model: {}", e))?;
+    let ctx = Arc::new(ctx);
+
+    // Another transcription may have loaded this model, or others, in the meantime
+    let mut cache = context_cache().lock().expect("CONTEXT_CACHE mutex poisoned");
+    if let Some(entry) = cache.get_mut(&key) {
+        entry.last_used = Instant::now();
+        log::info!("Whisper context {} was loaded concurrently; using that one", key);
+        return Ok((entry.ctx.clone(), false));
+    }
+    evict_contexts(&mut cache, size_bytes);
+    cache.insert(key, CachedContext { ctx: ctx.clone(), size_bytes, last_used: Instant::now() });
+    Ok((ctx, false))
+}
 
-        if src_idx + 1 < mono_samples.len() {
-            let sample = mono_samples[src_idx] * (1.0 - frac as f32)
-                + mono_samples[src_idx + 1] * frac as f32;
-            resampled.push(sample);
-        } else if src_idx < mono_samples.len() {
-            resampled.push(mono_samples[src_idx]);
+/// Unload a cached Whisper model (or all of them when `model_path` is None).
+/// Returns how many models were unloaded; running transcriptions keep theirs until done.
+#[tauri::command]
+pub async fn unload_whisper_model(model_path: Option<String>) -> Result<usize, String> {
+    let mut cache = context_cache().lock().expect("CONTEXT_CACHE mutex poisoned");
+    let unloaded = match model_path {
+        Some(path) => cache.remove(&path).map(|_| 1).unwrap_or(0),
+        None => {
+            let n = cache.len();
+            cache.clear();
+            n
         }
+    };
+    log::info!("Unloaded {} whisper model(s)", unloaded);
+    Ok(unloaded)
+}
+
+/// Language used when the caller doesn't pick one
+const DEFAULT_LANGUAGE: &str = "en";
+/// Audio used for language auto-detection (one Whisper window)
+const LANG_DETECT_SAMPLES: usize = 30 * 16000;
+
+/// Resolve the requested language ("auto", a code like "de", or None for English).
+/// Returns the language code to decode with and, for "auto", the detection probability.
+fn resolve_language(
+    ctx: &WhisperContext,
+    state: &mut WhisperState,
+    samples: &[f32],
+    requested: Option<&str>,
+    threads: usize,
+) -> Result<(String, Option<f32>), String> {
+    let requested = requested
+        .map(|l| l.trim().to_lowercase())
+        .filter(|l| !l.is_empty())
+        .unwrap_or_else(|| DEFAULT_LANGUAGE.to_string());
+
+    if requested != "auto" {
+        if whisper_rs::get_lang_id(&requested).is_none() {
+            return Err(format!("Unsupported language code: {}", requested));
+        }
+        if requested != "en" && !ctx.is_multilingual() {
+            return Err(format!(
+                "The selected model is English-only and cannot transcribe '{}'. Use a multilingual model (without .en).",
+                requested
+            ));
+        }
+        return Ok((requested, None));
+    }
+
+    if !ctx.is_multilingual() {
+        log::info!("Language auto-detection skipped: model is English-only");
+        return Ok((DEFAULT_LANGUAGE.to_string(), None));
     }
 
-    Ok(resampled)
+    let window = &samples[..samples.len().min(LANG_DETECT_SAMPLES)];
+    state.pcm_to_mel(window, threads)
+        .map_err(|e| format!("Language detection failed: {}", e))?;
+    let probs = state.lang_detect(0, threads)
+        .map_err(|e| format!("Language detection failed: {}", e))?;
+
+    let (id, probability) = probs
+        .iter()
+        .copied()
+        .enumerate()
+        .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+        .ok_or("Language detection returned no probabilities")?;
+    let code = whisper_rs::get_lang_str(id as i32)
+        .ok_or_else(|| format!("Unknown language id: {}", id))?;
+
+    log::info!("Detected language: {} (p={:.2})", code, probability);
+    Ok((code.to_string(), Some(probability)))
 }
 
-/// Public wrapper for load_audio_16khz (used by moonshine module)
-pub fn load_audio_16khz_pub(path: &Path) -> Result<Vec<f32>, String> {
-    load_audio_16khz(path)
+/// Running transcription sessions, keyed by session ID (for cancellation)
+pub struct TranscriptionSession {
+    cancel: Arc<AtomicBool>,
 }
 
-/// Transcribe audio file using Whisper
-#[tauri::command]
-pub async fn transcribe_audio(
-    path: String,
-    models_path: Option<String>,
-    beam_size: Option<i32>,
-    best_of: Option<i32>,
-    temperature: Option<f32>,
-) -> Result<TranscriptionResult, String> {
-    let audio_path = Path::new(&path);
-    let custom_path = models_path.as_deref();
+pub struct TranscriptionState {
+    sessions: Mutex<HashMap<String, TranscriptionSession>>,
+}
 
-    let total_start = Instant::now();
+impl TranscriptionState {
+    pub fn new() -> Self {
+        Self {
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+}
 
-    log::info!("Transcribing audio: {}", path);
-    log::info!("Models path: {:?}", custom_path);
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TranscriptionProgressEvent {
+    session_id: String,
+    /// "loading", "detecting_language", "decoding" or "complete"
+    stage: String,
+    /// 0.0 - 1.0 within the decoding stage
+    progress: f32,
+}
 
-    // Try to find any available model
-    let model_path = find_any_model(custom_path)?;
-    let model_name = model_path.file_name()
-        .map(|n| n.to_string_lossy().to_string())
-        .unwrap_or_else(|| "unknown".to_string());
+/// A segment as soon as Whisper has decoded it. Word timings are provisional
+/// (spread evenly over the segment) and confidences are the placeholder
+/// `FALLBACK_WORD_CONFIDENCE`, as the segment callback gets no token probabilities;
+/// the final result carries token-level timings and confidences.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TranscriptionSegmentEvent {
+    session_id: String,
+    segment_index: i32,
+    start: f64,
+    end: f64,
+    text: String,
+    words: Vec<Word>,
+}
 
-    log::info!("Using model: {:?}", model_path);
+pub(crate) fn emit_progress(app: &AppHandle, session_id: &str, stage: &str, progress: f32) {
+    let _ = app.emit("transcription-progress", TranscriptionProgressEvent {
+        session_id: session_id.to_string(),
+        stage: stage.to_string(),
+        progress,
+    });
+}
 
-    // Load audio
-    let samples = load_audio_16khz(audio_path)?;
-    let audio_duration_secs = samples.len() as f64 / 16000.0;
-    log::info!("Loaded {} samples at 16kHz ({:.1}s)", samples.len(), audio_duration_secs);
+/// Split segment text into words spread evenly over `[start, end]`
+fn split_segment_words(text: &str, start: f64, end: f64) -> Vec<(String, f64, f64)> {
+    let text_words: Vec<&str> = text.split_whitespace().collect();
+    if text_words.is_empty() {
+        return Vec::new();
+    }
+    let word_duration = (end - start) / text_words.len() as f64;
+    text_words
+        .iter()
+        .enumerate()
+        .map(|(j, w)| {
+            (
+                w.to_string(),
+                start + j as f64 * word_duration,
+                start + (j + 1) as f64 * word_duration,
+            )
+        })
+        .collect()
+}
 
-    // Create whisper context
-    let load_start = Instant::now();
-    let ctx = WhisperContext::new_with_params(
-        model_path.to_str()
-            .ok_or_else(|| format!("Model path contains invalid UTF-8: {:?}", model_path))?,
-        WhisperContextParameters::default(),
-    )
-    .map_err(|e| format!("Failed to load whisper model: {}", e))?;
+/// Error returned when a session is cancelled via `cancel_transcription`
+pub(crate) const CANCELLED_MESSAGE: &str = "Transcription cancelled";
+
+/// Long-form windowing: 5-minute windows cut at the quietest point of their last 30s
+const WHISPER_WINDOW: WindowConfig = WindowConfig { window: 300.0, overlap: 5.0, search: 30.0 };
+/// Characters of the previous window's text used as the next window's prompt
+const PROMPT_TAIL_CHARS: usize = 200;
+/// Longest vocabulary prompt passed to Whisper. Its prompt holds ~224 tokens and
+/// shares them with the previous window's tail.
+const VOCABULARY_PROMPT_MAX_CHARS: usize = 500;
+
+/// Whisper prompt text for a custom vocabulary: the initial prompt followed by the
+/// hotwords as a comma list. When too long, the start of the prompt is dropped first.
+fn vocabulary_prompt(initial_prompt: Option<&str>, hotwords: &[String]) -> Option<String> {
+    let hotwords: Vec<&str> = hotwords.iter().map(|w| w.trim()).filter(|w| !w.is_empty()).collect();
+    let mut parts: Vec<String> = Vec::new();
+    if let Some(p) = initial_prompt.map(str::trim).filter(|p| !p.is_empty()) {
+        parts.push(p.to_string());
+    }
+    if !hotwords.is_empty() {
+        parts.push(format!("{}.", hotwords.join(", ")));
+    }
+    if parts.is_empty() {
+        return None;
+    }
+    let prompt = parts.join(" ");
+    Some(chunking::text_tail(&prompt, VOCABULARY_PROMPT_MAX_CHARS).to_string())
+}
 
-    // Create state for this transcription
-    let mut state = ctx.create_state()
-        .map_err(|e| format!("Failed to create whisper state: {}", e))?;
+/// Prompt for a run: the explicit prompt/hotwords when given, otherwise the
+/// vocabulary stored next to the file's transcription metadata.
+fn resolve_vocabulary_prompt(path: &str, initial_prompt: Option<String>, hotwords: Option<Vec<String>>) -> Option<String> {
+    if initial_prompt.is_none() && hotwords.is_none() {
+        let stored = metadata::load_vocabulary(path)?;
+        log::info!("Using stored vocabulary ({} hotwords)", stored.hotwords.len());
+        return vocabulary_prompt(stored.initial_prompt.as_deref(), &stored.hotwords);
+    }
+    vocabulary_prompt(initial_prompt.as_deref(), &hotwords.unwrap_or_default())
+}
+
+/// Options for one Whisper run (mirrors the `transcribe_audio` arguments)
+struct WhisperRequest {
+    path: String,
+    models_path: Option<String>,
+    beam_size: Option<i32>,
+    best_of: Option<i32>,
+    temperature: Option<f32>,
+    language: Option<String>,
+    task: String,
+    start_time: Option<f64>,
+    end_time: Option<f64>,
+    /// Vocabulary prompt, repeated ahead of every window's context
+    vocabulary_prompt: Option<String>,
+    /// Model to use (default: the first one found)
+    model_path: Option<PathBuf>,
+}
 
-    // Configure parameters — use BeamSearch when beam_size > 1
-    let bs = beam_size.unwrap_or(1);
-    let bo = best_of.unwrap_or(1);
-    let temp = temperature.unwrap_or(0.0);
+/// Decoding settings shared by every window of a run
+struct DecodeOptions {
+    beam_size: i32,
+    best_of: i32,
+    temperature: f32,
+    translate: bool,
+    threads: i32,
+}
 
-    let mut params = if bs > 1 {
-        FullParams::new(SamplingStrategy::BeamSearch { beam_size: bs, patience: 1.0 })
+/// Whisper parameters for one window — use BeamSearch when beam_size > 1
+fn build_params<'a, 'b>(opts: &DecodeOptions, language: &'b str) -> FullParams<'a, 'b> {
+    let mut params = if opts.beam_size > 1 {
+        FullParams::new(SamplingStrategy::BeamSearch { beam_size: opts.beam_size, patience: 1.0 })
     } else {
-        FullParams::new(SamplingStrategy::Greedy { best_of: bo })
+        FullParams::new(SamplingStrategy::Greedy { best_of: opts.best_of })
     };
-    params.set_temperature(temp);
-    params.set_language(Some("en"));
+    params.set_temperature(opts.temperature);
+    params.set_language(Some(language));
+    params.set_translate(opts.translate);
     params.set_token_timestamps(true);
     params.set_print_special(false);
     params.set_print_progress(false);
     params.set_print_realtime(false);
     params.set_print_timestamps(false);
+    params.set_n_threads(opts.threads);
+    params
+}
 
-    // Limit threads to leave headroom for the system (UI, audio, OS)
-    let max_threads = std::thread::available_parallelism()
-        .map(|n| n.get())
-        .unwrap_or(4);
-    let whisper_threads = (max_threads / 2).max(1).min(8) as i32;
-    params.set_n_threads(whisper_threads);
-    log::info!("Whisper using {} threads (system has {})", whisper_threads, max_threads);
-    let load_time_ms = load_start.elapsed().as_millis() as u64;
+/// Report progress, stream decoded segments and poll the cancel flag from Whisper's callbacks.
+/// Times and progress are mapped from the window to the whole file.
+fn attach_callbacks(
+    params: &mut FullParams,
+    app: &AppHandle,
+    session_id: &str,
+    cancel: &Arc<AtomicBool>,
+    window: &AudioWindow,
+    expected_span: Option<(f64, f64)>,
+    segment_base: i32,
+) {
+    let (window_start, window_duration) = (window.start, window.duration());
+    let (keep_from, keep_to) = (window.keep_from, window.keep_to);
+
+    let progress_app = app.clone();
+    let progress_id = session_id.to_string();
+    params.set_progress_callback_safe(move |progress: i32| {
+        let within = progress as f64 / 100.0;
+        let overall = match expected_span {
+            Some((span_start, span)) if span > 0.0 => {
+                ((window_start - span_start + within * window_duration) / span).min(1.0)
+            }
+            _ => within,
+        };
+        emit_progress(&progress_app, &progress_id, "decoding", overall as f32);
+    });
+
+    let segment_app = app.clone();
+    let segment_id = session_id.to_string();
+    params.set_segment_callback_safe(move |data: whisper_rs::SegmentCallbackData| {
+        let start = window_start + data.start_timestamp as f64 / 100.0;
+        let end = window_start + data.end_timestamp as f64 / 100.0;
+        let words: Vec<Word> = split_segment_words(&data.text, start, end)
+            .into_iter()
+            .filter(|(_, s, e)| {
+                let mid = (s + e) / 2.0;
+                mid >= keep_from && mid < keep_to
+            })
+            .map(|(text, start, end)| Word {
+                id: uuid::Uuid::new_v4().to_string(),
+                text,
+                start,
+                end,
+                confidence: FALLBACK_WORD_CONFIDENCE,
+            })
+            .collect();
+        if words.is_empty() {
+            return;
+        }
+        let _ = segment_app.emit("transcription-segment", TranscriptionSegmentEvent {
+            session_id: segment_id.clone(),
+            segment_index: segment_base + data.segment,
+            start,
+            end,
+            text: data.text,
+            words,
+        });
+    });
 
-    // Run transcription
-    let inference_start = Instant::now();
-    state.full(params, &samples)
-        .map_err(|e| format!("Transcription failed: {}", e))?;
-    let inference_time_ms = inference_start.elapsed().as_millis() as u64;
+    let abort_flag = cancel.clone();
+    params.set_abort_callback_safe(move || abort_flag.load(Ordering::Relaxed));
+}
+
+/// Word confidence from its tokens' probabilities (geometric mean, so one unsure token pulls it down)
+fn word_confidence(token_probs: &[f32]) -> Option<f64> {
+    if token_probs.is_empty() {
+        return None;
+    }
+    let mean_log = token_probs.iter().map(|&p| (p.max(1e-6) as f64).ln()).sum::<f64>() / token_probs.len() as f64;
+    Some(mean_log.exp())
+}
+
+/// Confidence for words when Whisper returned no token probabilities
+const FALLBACK_WORD_CONFIDENCE: f64 = 0.9;
 
-    // Extract segments and create word-level timestamps
+/// Words and segments decoded for one window (times offset to the file)
+struct WindowDecode {
+    words: Vec<Word>,
+    segments: Vec<TranscriptionSegment>,
+    num_segments: i32,
+    skipped_segments: i32,
+}
+
+/// Words and segments from the last `state.full` run, shifted by `offset` seconds.
+/// Segments that fail to extract are skipped and counted in `skipped_segments`.
+fn collect_segment_words(state: &WhisperState, offset: f64) -> Result<WindowDecode, String> {
     let num_segments = state.full_n_segments()
         .map_err(|e| format!("Failed to get segments: {}", e))?;
 
     let mut words: Vec<Word> = Vec::new();
-    let mut full_text = String::new();
-
+    let mut segments: Vec<TranscriptionSegment> = Vec::new();
     let mut skipped_segments = 0;
 
     for i in 0..num_segments {
@@ -534,9 +770,11 @@ pub async fn transcribe_audio(
             }
         };
 
-        // Try to get word-level timestamps from tokens
-        let mut segment_words: Vec<(String, f64, f64)> = Vec::new();
+        // Try to get word-level timestamps and probabilities from tokens
+        let mut segment_words: Vec<(String, f64, f64, Option<f64>)> = Vec::new();
+        let mut segment_probs: Vec<f32> = Vec::new();
         let mut current_word = String::new();
+        let mut current_probs: Vec<f32> = Vec::new();
         let mut word_start: Option<f64> = None;
         let mut word_end = start_time;
 
@@ -559,19 +797,24 @@ pub async fn transcribe_audio(
             if starts_new_word && !current_word.is_empty() {
                 // Save current word
                 if let Some(start) = word_start {
-                    segment_words.push((current_word.clone(), start, word_end));
+                    segment_words.push((current_word.clone(), start, word_end, word_confidence(&current_probs)));
                 }
                 current_word.clear();
+                current_probs.clear();
                 word_start = None;
             }
 
-            // Get timestamp for this token
+            // Get timestamp and probability for this token
             if let Some(data) = token_data {
                 let t = data.t0 as f64 / 100.0;
                 if word_start.is_none() && !token_text.trim().is_empty() {
                     word_start = Some(t);
                 }
                 word_end = data.t1 as f64 / 100.0;
+                if !token_text.trim().is_empty() {
+                    current_probs.push(data.p);
+                    segment_probs.push(data.p);
+                }
             }
 
             // Add token text to current word
@@ -588,43 +831,520 @@ pub async fn transcribe_audio(
         // Don't forget the last word
         if !current_word.is_empty() {
             if let Some(start) = word_start {
-                segment_words.push((current_word, start, word_end));
+                segment_words.push((current_word, start, word_end, word_confidence(&current_probs)));
             }
         }
 
         // If we couldn't get word-level timestamps, fall back to splitting the segment
+        // (every word then gets the segment's confidence)
         if segment_words.is_empty() && !segment_text.trim().is_empty() {
-            let text_words: Vec<&str> = segment_text.split_whitespace().collect();
-            let duration = end_time - start_time;
-            let word_duration = if !text_words.is_empty() {
-                duration / text_words.len() as f64
-            } else {
-                duration
-            };
-
-            for (j, word_text) in text_words.iter().enumerate() {
-                let w_start = start_time + (j as f64 * word_duration);
-                let w_end = start_time + ((j + 1) as f64 * word_duration);
-                segment_words.push((word_text.to_string(), w_start, w_end));
-            }
+            let segment_confidence = word_confidence(&segment_probs);
+            segment_words = split_segment_words(&segment_text, start_time, end_time)
+                .into_iter()
+                .map(|(text, start, end)| (text, start, end, segment_confidence))
+                .collect();
         }
 
         // Add words to result
-        for (text, start, end) in segment_words {
-            if !full_text.is_empty() {
-                full_text.push(' ');
-            }
-            full_text.push_str(&text);
-
+        for (text, start, end, confidence) in segment_words {
             words.push(Word {
                 id: uuid::Uuid::new_v4().to_string(),
                 text,
-                start,
-                end,
-                confidence: 0.9, // Whisper doesn't provide per-word confidence
+                start: start + offset,
+                end: end + offset,
+                confidence: confidence.unwrap_or(FALLBACK_WORD_CONFIDENCE),
             });
         }
+
+        let no_speech_prob = state.full_get_segment_no_speech_prob(i).unwrap_or(0.0);
+        segments.push(TranscriptionSegment {
+            start: start_time + offset,
+            end: end_time + offset,
+            text: segment_text.trim().to_string(),
+            no_speech_prob,
+        });
+    }
+
+    Ok(WindowDecode { words, segments, num_segments, skipped_segments })
+}
+
+/// Transcribe audio file using Whisper.
+///
+/// Runs as a session: pass `session_id` to receive `transcription-progress` and
+/// `transcription-segment` events for it and to cancel it with `cancel_transcription`.
+/// With `start_time`/`end_time` only that region is decoded; word times stay absolute.
+/// `initial_prompt` and `hotwords` bias spelling of names and jargon; when neither is
+/// given, the vocabulary stored with the file's transcription metadata is used.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn transcribe_audio(
+    app: AppHandle,
+    path: String,
+    models_path: Option<String>,
+    beam_size: Option<i32>,
+    best_of: Option<i32>,
+    temperature: Option<f32>,
+    language: Option<String>,
+    task: Option<String>,
+    session_id: Option<String>,
+    start_time: Option<f64>,
+    end_time: Option<f64>,
+    initial_prompt: Option<String>,
+    hotwords: Option<Vec<String>>,
+) -> Result<TranscriptionResult, String> {
+    let task = task.unwrap_or_else(default_task);
+    if task != TASK_TRANSCRIBE && task != TASK_TRANSLATE {
+        return Err(format!("Unknown task '{}': expected \"transcribe\" or \"translate\"", task));
+    }
+    let vocabulary_prompt = resolve_vocabulary_prompt(&path, initial_prompt, hotwords);
+    let request = WhisperRequest {
+        path, models_path, beam_size, best_of, temperature, language, task, start_time, end_time, vocabulary_prompt,
+        model_path: None,
+    };
+    run_whisper_session(&app, request, session_id).await
+}
+
+/// Run one Whisper request as a cancellable session (see `transcribe_audio`)
+async fn run_whisper_session(
+    app: &AppHandle,
+    request: WhisperRequest,
+    session_id: Option<String>,
+) -> Result<TranscriptionResult, String> {
+    run_transcription_session(app, session_id, move |session_id, cancel, app| {
+        transcribe_whisper_blocking(request, session_id, cancel, app)
+    })
+    .await
+}
+
+/// Run a blocking transcription job as a session: registered for `cancel_transcription`
+/// while it runs, with a "complete" progress event when it succeeds
+pub(crate) async fn run_transcription_session<F>(
+    app: &AppHandle,
+    session_id: Option<String>,
+    job: F,
+) -> Result<TranscriptionResult, String>
+where
+    F: FnOnce(&str, &Arc<AtomicBool>, &AppHandle) -> Result<TranscriptionResult, String> + Send + 'static,
+{
+    let session_id = session_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let cancel = Arc::new(AtomicBool::new(false));
+
+    {
+        let state = app.state::<TranscriptionState>();
+        let mut sessions = state.sessions.lock().expect("transcription sessions mutex poisoned");
+        sessions.insert(session_id.clone(), TranscriptionSession { cancel: cancel.clone() });
+    }
+
+    let bg_app = app.clone();
+    let bg_session_id = session_id.clone();
+    let result = tokio::task::spawn_blocking(move || job(&bg_session_id, &cancel, &bg_app))
+    .await
+    .map_err(|e| format!("Transcription task failed: {}", e))
+    .and_then(|r| r);
+
+    let state = app.state::<TranscriptionState>();
+    state.sessions.lock().expect("transcription sessions mutex poisoned").remove(&session_id);
+
+    if result.is_ok() {
+        emit_progress(app, &session_id, "complete", 1.0);
+    }
+    result
+}
+
+/// Magic number at the start of whisper.cpp GGML model files ("ggml", little-endian)
+const GGML_MAGIC: u32 = 0x6767_6d6c;
+/// Vocabulary size of the English-only Whisper models
+const ENGLISH_ONLY_VOCAB: i32 = 51864;
+
+/// The hyperparameters of a GGML model we use to validate and name it
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct GgmlHeader {
+    n_vocab: i32,
+    n_audio_layer: i32,
+    n_mels: i32,
+}
+
+/// Parse the magic and hyperparameters at the start of a whisper.cpp model file
+/// (magic, then n_vocab, n_audio_ctx, n_audio_state, n_audio_head, n_audio_layer,
+/// n_text_ctx, n_text_state, n_text_head, n_text_layer, n_mels, ftype as i32s)
+fn parse_ggml_header(bytes: &[u8]) -> Result<GgmlHeader, String> {
+    if bytes.len() < 48 {
+        return Err("File is too small to be a Whisper model".to_string());
+    }
+    let field = |i: usize| i32::from_le_bytes([bytes[i * 4], bytes[i * 4 + 1], bytes[i * 4 + 2], bytes[i * 4 + 3]]);
+    if field(0) as u32 != GGML_MAGIC {
+        return Err("Not a whisper.cpp GGML model (bad magic number)".to_string());
+    }
+    let header = GgmlHeader { n_vocab: field(1), n_audio_layer: field(5), n_mels: field(10) };
+    if header.n_vocab <= 0 || header.n_audio_layer <= 0 || !matches!(header.n_mels, 80 | 128) {
+        return Err(format!("Unexpected Whisper model parameters: {:?}", header));
+    }
+    Ok(header)
+}
+
+/// Model name implied by a header ("base.en", "large-v3", ...), if it's a standard size
+fn ggml_model_name(header: &GgmlHeader) -> Option<String> {
+    let size = match header.n_audio_layer {
+        4 => "tiny",
+        6 => "base",
+        12 => "small",
+        24 => "medium",
+        32 if header.n_mels == 128 => "large-v3",
+        32 => "large",
+        _ => return None,
+    };
+    Some(if header.n_vocab == ENGLISH_ONLY_VOCAB { format!("{}.en", size) } else { size.to_string() })
+}
+
+/// Whisper (whisper.cpp GGML models) behind the common engine interface
+pub struct WhisperEngine;
+
+impl TranscriptionEngine for WhisperEngine {
+    fn id(&self) -> &'static str {
+        "whisper"
+    }
+
+    fn name(&self) -> &'static str {
+        "Whisper"
+    }
+
+    fn capabilities(&self) -> EngineCapabilities {
+        EngineCapabilities {
+            languages: Vec::new(),
+            language_detection: true,
+            translation: true,
+            vocabulary: true,
+            decoding_options: true,
+        }
+    }
+
+    fn list_models(&self, locations: &ModelLocations) -> Vec<EngineModel> {
+        whisper_models(locations.custom_path(), locations.resource_dir())
+            .into_iter()
+            .map(|m| EngineModel {
+                name: m.name,
+                available: m.available,
+                path: m.path,
+                size_mb: m.size_mb,
+                download_url: Some(m.download_url).filter(|u| !u.is_empty()),
+            })
+            .collect()
+    }
+
+    fn check_model(&self, model: Option<&str>, locations: &ModelLocations) -> Result<PathBuf, String> {
+        match model {
+            Some(name) => find_model_path_internal(name, locations.custom_path(), locations.resource_dir()),
+            None => locate_default_model(locations.custom_path(), locations.resource_dir()),
+        }
+    }
+
+    fn transcribe(
+        &self,
+        job: &TranscriptionJob,
+        progress: &ProgressReporter,
+        cancel: &Arc<AtomicBool>,
+    ) -> Result<TranscriptionResult, String> {
+        let options = &job.options;
+        let request = WhisperRequest {
+            path: job.path.clone(),
+            models_path: job.locations.models_path.clone(),
+            beam_size: options.beam_size,
+            best_of: options.best_of,
+            temperature: options.temperature,
+            language: options.language.clone(),
+            task: options.task.clone().unwrap_or_else(default_task),
+            start_time: job.start_time,
+            end_time: job.end_time,
+            vocabulary_prompt: resolve_vocabulary_prompt(&job.path, options.initial_prompt.clone(), options.hotwords.clone()),
+            model_path: Some(self.check_model(options.model.as_deref(), &job.locations)?),
+        };
+        transcribe_whisper_blocking(request, progress.session_id(), cancel, progress.app())
+    }
+
+    fn install_model(&self, source: &Path, name: Option<&str>, models_dir: &Path) -> Result<PathBuf, String> {
+        use std::io::Read;
+        let mut head = Vec::with_capacity(48);
+        std::fs::File::open(source)
+            .and_then(|f| f.take(48).read_to_end(&mut head))
+            .map_err(|e| format!("Failed to read {:?}: {}", source, e))?;
+        let header = parse_ggml_header(&head)?;
+        let name = match name {
+            Some(name) => name.to_string(),
+            None => ggml_model_name(&header)
+                .ok_or("Unrecognised Whisper model size; pass a name for it")?,
+        };
+        super::models::validate_model_name(&name)?;
+        super::models::install_copy(source, &models_dir.join(format!("ggml-{}.bin", name)))
+    }
+
+    fn installed_model(&self, name: &str, models_dir: &Path) -> Option<PathBuf> {
+        get_model_filenames(name)
+            .into_iter()
+            .map(|f| models_dir.join(f))
+            .find(|p| p.exists())
+            .or_else(|| scan_for_models(models_dir).into_iter().find(|(found, _)| found == name).map(|(_, p)| p))
+    }
+
+    fn unload_model(&self, path: &Path) {
+        let key = path.to_string_lossy().to_string();
+        if context_cache().lock().expect("CONTEXT_CACHE mutex poisoned").remove(&key).is_some() {
+            log::info!("Unloaded whisper model {}", key);
+        }
+    }
+}
+
+/// Time a known script (e.g. a narrator's text) against the audio.
+///
+/// Whisper transcribes the audio, then the script is aligned onto the recognised
+/// words by edit distance: matching words take Whisper's timings and words it
+/// missed are spread over the gaps. The result carries the script's exact words.
+/// Runs as a session like `transcribe_audio`.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn align_transcript(
+    app: AppHandle,
+    path: String,
+    text: String,
+    models_path: Option<String>,
+    language: Option<String>,
+    session_id: Option<String>,
+    start_time: Option<f64>,
+    end_time: Option<f64>,
+) -> Result<TranscriptionResult, String> {
+    if text.split_whitespace().next().is_none() {
+        return Err("Transcript text is empty".to_string());
+    }
+
+    let vocabulary_prompt = resolve_vocabulary_prompt(&path, None, None);
+    let request = WhisperRequest {
+        path,
+        models_path,
+        beam_size: None,
+        best_of: None,
+        temperature: None,
+        language,
+        task: TASK_TRANSCRIBE.to_string(),
+        start_time,
+        end_time,
+        vocabulary_prompt,
+        model_path: None,
+    };
+    let recognized = run_whisper_session(&app, request, session_id).await?;
+
+    let range_start = start_time.unwrap_or(0.0);
+    let audio_duration_secs = recognized.metrics.as_ref().map(|m| m.audio_duration_secs).unwrap_or(0.0);
+    let range_end = recognized
+        .words
+        .last()
+        .map(|w| w.end)
+        .unwrap_or(range_start)
+        .max(range_start + audio_duration_secs);
+    let words = alignment::align_script(&text, &recognized.words, (range_start, range_end));
+
+    let matched = words.iter().filter(|w| w.confidence > 0.5).count();
+    log::info!("Aligned transcript: {}/{} script words matched recognised words", matched, words.len());
+
+    let metrics = recognized.metrics.map(|m| TranscriptionMetrics {
+        word_count: words.len(),
+        words_per_second: if m.audio_duration_secs > 0.0 { words.len() as f64 / m.audio_duration_secs } else { 0.0 },
+        ..m
+    });
+    Ok(TranscriptionResult {
+        text: words.iter().map(|w| w.text.as_str()).collect::<Vec<_>>().join(" "),
+        words,
+        metrics,
+        ..recognized
+    })
+}
+
+/// Default threshold for `find_low_confidence_words`
+const DEFAULT_LOW_CONFIDENCE_THRESHOLD: f64 = 0.5;
+
+/// A word worth proofreading
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LowConfidenceWord {
+    /// Position of the word in the transcript
+    pub index: usize,
+    pub word_id: String,
+    pub text: String,
+    pub start: f64,
+    pub end: f64,
+    pub confidence: f64,
+}
+
+fn low_confidence_words(words: &[Word], threshold: f64) -> Vec<LowConfidenceWord> {
+    words
+        .iter()
+        .enumerate()
+        .filter(|(_, w)| w.confidence < threshold)
+        .map(|(index, w)| LowConfidenceWord {
+            index,
+            word_id: w.id.clone(),
+            text: w.text.clone(),
+            start: w.start,
+            end: w.end,
+            confidence: w.confidence,
+        })
+        .collect()
+}
+
+/// List words recognised with less than `threshold` confidence (default 0.5), in transcript order
+#[tauri::command]
+pub async fn find_low_confidence_words(
+    words: Vec<Word>,
+    threshold: Option<f64>,
+) -> Result<Vec<LowConfidenceWord>, String> {
+    let threshold = threshold.unwrap_or(DEFAULT_LOW_CONFIDENCE_THRESHOLD);
+    if !(0.0..=1.0).contains(&threshold) {
+        return Err(format!("Confidence threshold must be between 0 and 1, got {}", threshold));
+    }
+    Ok(low_confidence_words(&words, threshold))
+}
+
+/// Cancel a running transcription session
+#[tauri::command]
+pub async fn cancel_transcription(app: AppHandle, session_id: String) -> Result<(), String> {
+    let state = app.state::<TranscriptionState>();
+    let sessions = state.sessions.lock().expect("transcription sessions mutex poisoned");
+    if let Some(session) = sessions.get(&session_id) {
+        log::info!("Cancelling transcription session {}", session_id);
+        session.cancel.store(true, Ordering::Relaxed);
+    }
+    Ok(())
+}
+
+fn transcribe_whisper_blocking(
+    request: WhisperRequest,
+    session_id: &str,
+    cancel: &Arc<AtomicBool>,
+    app: &AppHandle,
+) -> Result<TranscriptionResult, String> {
+    let WhisperRequest {
+        path, models_path, beam_size, best_of, temperature, language, task, start_time, end_time, vocabulary_prompt,
+        model_path,
+    } = request;
+    let audio_path = Path::new(&path);
+    let custom_path = models_path.as_deref();
+
+    let total_start = Instant::now();
+
+    log::info!("Transcribing audio: {} (task: {}, range: {:?} - {:?})", path, task, start_time, end_time);
+    log::info!("Models path: {:?}", custom_path);
+
+    // Use the requested model, or the default one
+    let model_path = match model_path {
+        Some(p) => p,
+        None => locate_default_model(custom_path, app.path().resource_dir().ok().as_deref())?,
+    };
+    let model_name = model_path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    log::info!("Using model: {:?}", model_path);
+
+    // Stream audio in overlapping windows so memory stays flat for very long recordings
+    emit_progress(app, session_id, "loading", 0.0);
+    let stream = Stream16k::open_range(audio_path, start_time, end_time)?;
+    let range_start = start_time.unwrap_or(0.0);
+    let expected_span = stream.duration_secs().map(|d| (range_start, d));
+    let mut windows = Windower::new(stream, WHISPER_WINDOW).starting_at(range_start);
+    let first_window = windows.next_window()?.ok_or("No audio decoded from file")?;
+    if cancel.load(Ordering::Relaxed) {
+        return Err(CANCELLED_MESSAGE.to_string());
+    }
+
+    // Get (or load) the whisper context
+    let load_start = Instant::now();
+    let (ctx, cached) = get_or_load_context(&model_path)?;
+    log::info!("Whisper context ready in {}ms (cached: {})", load_start.elapsed().as_millis(), cached);
+
+    // Create state for this transcription
+    let mut state = ctx.create_state()
+        .map_err(|e| format!("Failed to create whisper state: {}", e))?;
+
+    // Limit threads to leave headroom for the system (UI, audio, OS)
+    let max_threads = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(4);
+    let whisper_threads = (max_threads / 2).max(1).min(8) as i32;
+
+    if language.as_deref().map(|l| l.trim().eq_ignore_ascii_case("auto")).unwrap_or(false) {
+        emit_progress(app, session_id, "detecting_language", 0.0);
+    }
+    let (language, language_probability) = resolve_language(
+        &ctx,
+        &mut state,
+        &first_window.samples,
+        language.as_deref(),
+        whisper_threads as usize,
+    )?;
+    let translate = task == TASK_TRANSLATE;
+    if translate && !ctx.is_multilingual() {
+        return Err("The selected model is English-only and cannot translate. Use a multilingual model (without .en).".to_string());
+    }
+
+    let decode = DecodeOptions {
+        beam_size: beam_size.unwrap_or(1),
+        best_of: best_of.unwrap_or(1),
+        temperature: temperature.unwrap_or(0.0),
+        translate,
+        threads: whisper_threads,
+    };
+    log::info!("Whisper using {} threads (system has {})", whisper_threads, max_threads);
+    let load_time_ms = load_start.elapsed().as_millis() as u64;
+
+    // Run transcription window by window, prompting each with the end of the previous one
+    emit_progress(app, session_id, "decoding", 0.0);
+    let inference_start = Instant::now();
+    let mut words: Vec<Word> = Vec::new();
+    let mut segments: Vec<TranscriptionSegment> = Vec::new();
+    let mut num_segments = 0;
+    let mut skipped_segments = 0;
+    let mut window_count = 0;
+    let mut audio_duration_secs = 0.0;
+    let mut prompt = String::new();
+    let mut next_window = Some(first_window);
+
+    while let Some(window) = next_window {
+        if cancel.load(Ordering::Relaxed) {
+            return Err(CANCELLED_MESSAGE.to_string());
+        }
+
+        let mut params = build_params(&decode, &language);
+        let window_prompt = match &vocabulary_prompt {
+            Some(vocab) if !prompt.is_empty() => format!("{} {}", vocab, prompt),
+            Some(vocab) => vocab.clone(),
+            None => prompt.clone(),
+        };
+        if !window_prompt.is_empty() {
+            params.set_initial_prompt(&window_prompt);
+        }
+        attach_callbacks(&mut params, app, session_id, cancel, &window, expected_span, num_segments);
+
+        let full_result = state.full(params, &window.samples);
+        if cancel.load(Ordering::Relaxed) {
+            log::info!("Transcription session {} cancelled", session_id);
+            return Err(CANCELLED_MESSAGE.to_string());
+        }
+        full_result.map_err(|e| format!("Transcription failed: {}", e))?;
+
+        let decoded = collect_segment_words(&state, window.start)?;
+        num_segments += decoded.num_segments;
+        skipped_segments += decoded.skipped_segments;
+        segments.extend(decoded.segments.into_iter().filter(|seg| window.owns(seg.start, seg.end)));
+
+        let kept = chunking::keep_owned(&window, decoded.words);
+        let kept_text = kept.iter().map(|w| w.text.as_str()).collect::<Vec<_>>().join(" ");
+        prompt = chunking::text_tail(&kept_text, PROMPT_TAIL_CHARS).to_string();
+        words.extend(kept);
+
+        audio_duration_secs = window.start + window.duration() - range_start;
+        window_count += 1;
+        next_window = windows.next_window()?;
     }
+    let inference_time_ms = inference_start.elapsed().as_millis() as u64;
+    let full_text = words.iter().map(|w| w.text.as_str()).collect::<Vec<_>>().join(" ");
 
     if skipped_segments > 0 {
         log::warn!("Transcription finished with {} skipped segments (out of {})", skipped_segments, num_segments);
@@ -636,14 +1356,17 @@ pub async fn transcribe_audio(
     let real_time_factor = if audio_duration_secs > 0.0 { (total_time_ms as f64 / 1000.0) / audio_duration_secs } else { 0.0 };
 
     log::info!(
-        "Whisper transcription complete: {} words from {} segments | load={}ms inference={}ms total={}ms | RTF={:.2}x",
-        word_count, num_segments - skipped_segments, load_time_ms, inference_time_ms, total_time_ms, real_time_factor
+        "Whisper transcription complete: {} words from {} segments in {} windows | load={}ms inference={}ms total={}ms | RTF={:.2}x",
+        word_count, num_segments - skipped_segments, window_count, load_time_ms, inference_time_ms, total_time_ms, real_time_factor
     );
 
     Ok(TranscriptionResult {
         words,
         text: full_text,
-        language: "en".to_string(),
+        language,
+        language_probability,
+        task,
+        segments,
         metrics: Some(TranscriptionMetrics {
             engine: "whisper".to_string(),
             model_name,
@@ -661,25 +1384,39 @@ pub async fn transcribe_audio(
 /// Check if whisper model is available
 #[tauri::command]
 pub async fn check_whisper_model(app_handle: AppHandle, custom_path: Option<String>) -> Result<String, String> {
-    let path_ref = custom_path.as_deref();
+    let resource_dir = app_handle.path().resource_dir().ok();
+    locate_default_model(custom_path.as_deref(), resource_dir.as_deref())
+        .map(|p| p.to_string_lossy().to_string())
+}
+
+/// The model used when none is given: the default set for Whisper, else any installed
+/// model, else a bundled tiny model
+fn locate_default_model(path_ref: Option<&str>, resource_dir: Option<&Path>) -> Result<PathBuf, String> {
     log::info!("Checking for whisper model, custom path: {:?}", path_ref);
 
+    if let Some(name) = super::models::default_model("whisper") {
+        match find_model_path_internal(&name, path_ref, resource_dir) {
+            Ok(path) => return Ok(path),
+            Err(e) => log::warn!("Default whisper model {} not available: {}", name, e),
+        }
+    }
+
     // Try find_any_model first (custom path, app data, system locations)
     if let Ok(path) = find_any_model(path_ref) {
-        return Ok(path.to_string_lossy().to_string());
+        return Ok(path);
     }
 
     // Also check bundled resources (production)
-    if let Ok(resource_dir) = app_handle.path().resource_dir() {
+    if let Some(resource_dir) = resource_dir {
         let bundled = resource_dir.join("models").join("ggml-tiny.bin");
         if bundled.exists() {
             log::info!("Found bundled model: {:?}", bundled);
-            return Ok(bundled.to_string_lossy().to_string());
+            return Ok(bundled);
         }
         let bundled_en = resource_dir.join("models").join("ggml-tiny.en.bin");
         if bundled_en.exists() {
             log::info!("Found bundled model: {:?}", bundled_en);
-            return Ok(bundled_en.to_string_lossy().to_string());
+            return Ok(bundled_en);
         }
     }
 
@@ -690,7 +1427,7 @@ pub async fn check_whisper_model(app_handle: AppHandle, custom_path: Option<Stri
                 let exe_model = exe_dir.join("models").join(model_file);
                 if exe_model.exists() {
                     log::info!("Found model next to executable: {:?}", exe_model);
-                    return Ok(exe_model.to_string_lossy().to_string());
+                    return Ok(exe_model);
                 }
             }
         }
@@ -704,7 +1441,7 @@ pub async fn check_whisper_model(app_handle: AppHandle, custom_path: Option<Stri
         let dev_model = dev_models_dir.join(model_file);
         if dev_model.exists() {
             log::info!("Found model at dev path: {:?}", dev_model);
-            return Ok(dev_model.to_string_lossy().to_string());
+            return Ok(dev_model);
         }
     }
 
@@ -751,23 +1488,25 @@ pub async fn debug_list_directory(path: String) -> Result<Vec<String>, String> {
 /// List all available whisper models with their status
 #[tauri::command]
 pub async fn list_available_models(app_handle: AppHandle, custom_path: Option<String>) -> Result<Vec<ModelInfo>, String> {
-    let path_ref = custom_path.as_deref();
-    let mut models = Vec::new();
-
     // Get bundled resource directory for checking bundled models
     let resource_dir = app_handle.path().resource_dir().ok();
+    Ok(whisper_models(custom_path.as_deref(), resource_dir.as_deref()))
+}
 
-    // Also scan custom directory for additional models
-    let mut found_in_custom: Vec<(String, std::path::PathBuf)> = Vec::new();
-    if let Some(custom) = path_ref {
-        if !custom.is_empty() {
-            found_in_custom = scan_for_models(Path::new(custom));
-            log::info!("Scanned custom path, found: {:?}", found_in_custom);
-        }
+/// Known models plus any extra ones found in the custom directory
+fn whisper_models(path_ref: Option<&str>, resource_dir: Option<&Path>) -> Vec<ModelInfo> {
+    let mut models = Vec::new();
+
+    // Also scan the managed directories (custom path, app data) for additional models
+    let mut found_extra: Vec<(String, std::path::PathBuf)> = Vec::new();
+    for dir in super::models::managed_dirs(path_ref) {
+        let found = scan_for_models(&dir);
+        log::info!("Scanned {:?}, found: {:?}", dir, found);
+        found_extra.extend(found);
     }
 
     for (name, filenames, size_mb) in WHISPER_MODELS {
-        let found_path = find_model_path_internal(name, path_ref, resource_dir.as_deref()).ok();
+        let found_path = find_model_path_internal(name, path_ref, resource_dir).ok();
         let available = found_path.is_some();
 
         models.push(ModelInfo {
@@ -783,8 +1522,8 @@ pub async fn list_available_models(app_handle: AppHandle, custom_path: Option<St
         });
     }
 
-    // Add any models found in custom path that aren't in our predefined list
-    for (found_name, found_path) in found_in_custom {
+    // Add any models found there that aren't in our predefined list
+    for (found_name, found_path) in found_extra {
         let already_listed = models.iter().any(|m| {
             m.path.as_ref().map(|p| p == &found_path.to_string_lossy().to_string()).unwrap_or(false)
         });
@@ -806,7 +1545,7 @@ pub async fn list_available_models(app_handle: AppHandle, custom_path: Option<St
         }
     }
 
-    Ok(models)
+    models
 }
 
 /// Check if a bundled model exists
@@ -851,13 +1590,35 @@ pub fn get_bundled_model_info(app_handle: AppHandle) -> Result<Option<String>, S
     Ok(None)
 }
 
-/// Download a model file to the specified directory
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ModelDownloadProgressEvent {
+    filename: String,
+    downloaded: u64,
+    total: Option<u64>,
+}
+
+/// Emit a progress event at most once per this many bytes
+const DOWNLOAD_PROGRESS_STEP: u64 = 1024 * 1024;
+
+/// Download a model file to the specified directory.
+///
+/// Resumes an interrupted download of the same file and emits `model-download-progress`
+/// events. The file is checked against `sha256` (or the entry for `filename` in a
+/// `model-manifest.json` next to the bundled or installed models) before it is moved
+/// into place, so a partial or corrupt download is never picked up as a model.
 #[tauri::command]
 pub async fn download_model(
+    app: AppHandle,
     url: String,
     filename: String,
     custom_path: Option<String>,
+    sha256: Option<String>,
 ) -> Result<String, String> {
+    if Path::new(&filename).file_name().and_then(|n| n.to_str()) != Some(filename.as_str()) {
+        return Err(format!("Invalid model filename: {}", filename));
+    }
+
     // Determine target directory
     let target_dir = if let Some(custom) = custom_path.as_ref().filter(|p| !p.is_empty()) {
         std::path::PathBuf::from(custom)
@@ -871,29 +1632,138 @@ pub async fn download_model(
             .map_err(|e| format!("Failed to create directory: {}", e))?;
     }
 
+    let mut manifests = vec![target_dir.join(model_download::MANIFEST_FILENAME)];
+    if let Ok(models_dir) = get_models_dir() {
+        manifests.push(models_dir.join(model_download::MANIFEST_FILENAME));
+    }
+    if let Ok(resource_dir) = app.path().resource_dir() {
+        manifests.push(resource_dir.join("models").join(model_download::MANIFEST_FILENAME));
+    }
+    let expected = match sha256.filter(|h| !h.trim().is_empty()) {
+        Some(sha256) => model_download::ExpectedFile { sha256: Some(sha256), size: None },
+        None => model_download::find_manifest_entry(&manifests, &filename)
+            .map(|entry| model_download::ExpectedFile::from(&entry))
+            .unwrap_or_default(),
+    };
+
     let target_path = target_dir.join(&filename);
-    log::info!("Downloading {} to {:?}", url, target_path);
+    log::info!("Downloading {} to {:?} (checksum known: {})", url, target_path, expected.sha256.is_some());
+
+    let client = reqwest::Client::new();
+    let mut last_emitted: Option<u64> = None;
+    let outcome = model_download::download_verified(&client, &url, &target_path, &expected, |downloaded, total| {
+        let due = match last_emitted {
+            Some(last) => downloaded >= last + DOWNLOAD_PROGRESS_STEP || Some(downloaded) == total,
+            None => true,
+        };
+        if due {
+            last_emitted = Some(downloaded);
+            let _ = app.emit("model-download-progress", ModelDownloadProgressEvent {
+                filename: filename.clone(),
+                downloaded,
+                total,
+            });
+        }
+    })
+    .await?;
 
-    // Download the file
-    let response = reqwest::get(&url)
-        .await
-        .map_err(|e| format!("Failed to start download: {}", e))?;
+    log::info!(
+        "Download complete: {:?} ({} bytes, resumed from {}, sha256 {})",
+        outcome.path, outcome.size, outcome.resumed_from, outcome.sha256
+    );
+    Ok(outcome.path.to_string_lossy().to_string())
+}
 
-    if !response.status().is_success() {
-        return Err(format!("Download failed with status: {}", response.status()));
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    const GB: u64 = 1024 * 1024 * 1024;
+
+    #[test]
+    fn test_vocabulary_prompt() {
+        let hotwords = vec!["Tauri".to_string(), " ".to_string(), "Kubernetes".to_string()];
+        assert_eq!(
+            vocabulary_prompt(Some("Episode 12 of the show."), &hotwords).as_deref(),
+            Some("Episode 12 of the show. Tauri, Kubernetes.")
+        );
+        assert_eq!(vocabulary_prompt(None, &hotwords).as_deref(), Some("Tauri, Kubernetes."));
+        assert_eq!(vocabulary_prompt(Some("  "), &[]), None);
+
+        // Over-long prompts keep the hotwords at the end
+        let long = "word ".repeat(200);
+        let prompt = vocabulary_prompt(Some(&long), &hotwords).unwrap();
+        assert!(prompt.len() <= VOCABULARY_PROMPT_MAX_CHARS);
+        assert!(prompt.ends_with("Tauri, Kubernetes."));
     }
 
-    let bytes = response.bytes()
-        .await
-        .map_err(|e| format!("Failed to download file: {}", e))?;
+    #[test]
+    fn test_word_confidence_geometric_mean() {
+        assert_eq!(word_confidence(&[]), None);
+        assert!((word_confidence(&[0.9]).unwrap() - 0.9).abs() < 1e-6);
+        // One unsure token pulls the word well below the arithmetic mean (0.55)
+        let c = word_confidence(&[1.0, 0.1]).unwrap();
+        assert!((c - 0.316).abs() < 0.01, "got {}", c);
+    }
 
-    // Write to file
-    let mut file = File::create(&target_path)
-        .map_err(|e| format!("Failed to create file: {}", e))?;
+    #[test]
+    fn test_low_confidence_words() {
+        let word = |id: &str, confidence: f64| Word { confidence, ..test_word(id, id, 0.0, 0.1) };
+        let words = vec![word("a", 0.95), word("b", 0.3), word("c", 0.7), word("d", 0.1)];
+        let low = low_confidence_words(&words, 0.5);
+        assert_eq!(low.iter().map(|w| (w.index, w.word_id.as_str())).collect::<Vec<_>>(), vec![(1, "b"), (3, "d")]);
+    }
 
-    file.write_all(&bytes)
-        .map_err(|e| format!("Failed to write file: {}", e))?;
+    #[test]
+    fn test_parse_ggml_header() {
+        let header = |fields: [i32; 11]| {
+            let mut bytes = GGML_MAGIC.to_le_bytes().to_vec();
+            bytes.extend(fields.iter().flat_map(|f| f.to_le_bytes()));
+            bytes
+        };
+        let base_en = parse_ggml_header(&header([51864, 1500, 512, 8, 6, 448, 512, 8, 6, 80, 1])).unwrap();
+        assert_eq!(ggml_model_name(&base_en).as_deref(), Some("base.en"));
+        let large_v3 = parse_ggml_header(&header([51866, 1500, 1280, 20, 32, 448, 1280, 20, 32, 128, 1])).unwrap();
+        assert_eq!(ggml_model_name(&large_v3).as_deref(), Some("large-v3"));
+
+        assert!(parse_ggml_header(b"PK\x03\x04 not a model at all, just a zip file header.....").is_err());
+        assert!(parse_ggml_header(&header([51864, 1500, 512, 8, 6, 448, 512, 8, 6, 99, 1])).is_err());
+        assert!(parse_ggml_header(&GGML_MAGIC.to_le_bytes()).is_err());
+    }
 
-    log::info!("Download complete: {:?}", target_path);
-    Ok(target_path.to_string_lossy().to_string())
+    #[test]
+    fn test_parse_mem_available() {
+        let meminfo = "MemTotal:       16318480 kB\nMemFree:         1204012 kB\nMemAvailable:    8123456 kB\n";
+        assert_eq!(parse_mem_available(meminfo), Some(8123456 * 1024));
+        assert_eq!(parse_mem_available("MemTotal: 1 kB\n"), None);
+    }
+
+    #[test]
+    fn test_plan_evictions_lru_until_model_fits() {
+        let now = Instant::now();
+        let entries = vec![
+            ("old".to_string(), 3 * GB, now),
+            ("new".to_string(), GB / 2, now + Duration::from_secs(10)),
+        ];
+        // Plenty of memory: nothing to evict (but the count cap still applies)
+        assert_eq!(plan_evictions(&entries[1..], GB, Some(16 * GB)), Vec::<String>::new());
+        assert_eq!(plan_evictions(&entries, GB, Some(16 * GB)), vec!["old".to_string()]);
+        // Tight memory: the least recently used model goes first
+        assert_eq!(plan_evictions(&entries[..1], 2 * GB, Some(GB)), vec!["old".to_string()]);
+        // Unknown memory: fall back to the fixed budget
+        assert_eq!(plan_evictions(&entries[..1], GB / 2, None), Vec::<String>::new());
+        assert_eq!(plan_evictions(&entries[..1], 2 * GB, None), vec!["old".to_string()]);
+    }
+
+    #[test]
+    fn test_installed_model_in_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("ggml-base.en.bin"), b"model").unwrap();
+        std::fs::write(dir.path().join("ggml-custom_q5.bin"), b"model").unwrap();
+        let engine = WhisperEngine;
+        assert_eq!(engine.installed_model("base", dir.path()), Some(dir.path().join("ggml-base.en.bin")));
+        assert_eq!(engine.installed_model("custom_q5", dir.path()), Some(dir.path().join("ggml-custom_q5.bin")));
+        assert_eq!(engine.installed_model("tiny", dir.path()), None);
+    }
 }
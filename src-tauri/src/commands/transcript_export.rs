@@ -0,0 +1,323 @@
+//! Transcript interchange: Audacity label tracks, Praat TextGrid and plain text
+
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::fs;
+
+use super::metadata::{TranscribedWord, TranscriptionMetadata};
+use super::subtitles::format_timestamp;
+
+/// Pause that ends a segment when grouping words into phrases
+const SEGMENT_PAUSE_SECS: f64 = 0.5;
+/// Imported labels longer than this are treated as regions, not words
+const MAX_IMPORTED_WORD_SECS: f64 = 2.0;
+
+/// A labelled time range (an Audacity region or a transcript segment)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LabelRegion {
+    pub start: f64,
+    pub end: f64,
+    pub label: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptExportResult {
+    pub output_path: String,
+    pub format: String,
+    /// Labels, intervals or lines written
+    pub item_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudacityLabelImport {
+    /// Single-word labels, as transcript words
+    pub words: Vec<TranscribedWord>,
+    /// Everything else: point labels, phrases and long regions
+    pub regions: Vec<LabelRegion>,
+}
+
+/// Transcript words with the metadata's global offset and per-word adjustments applied
+fn adjusted_words(metadata: &TranscriptionMetadata) -> Vec<TranscribedWord> {
+    let word_shift = metadata.word_shift();
+    let mut words: Vec<TranscribedWord> = metadata
+        .words
+        .as_deref()
+        .unwrap_or_default()
+        .iter()
+        .filter(|w| !w.text.trim().is_empty())
+        .map(|w| {
+            let shift = word_shift(w.id.as_str());
+            TranscribedWord {
+                start: (w.start + shift).max(0.0),
+                end: (w.end + shift).max(0.0),
+                text: w.text.trim().to_string(),
+                ..w.clone()
+            }
+        })
+        .collect();
+    words.sort_by(|a, b| a.start.partial_cmp(&b.start).unwrap_or(std::cmp::Ordering::Equal));
+    words
+}
+
+/// Group words into phrases, breaking at pauses, sentence ends and speaker changes
+fn group_segments(words: &[TranscribedWord]) -> Vec<(LabelRegion, Option<String>)> {
+    let mut segments: Vec<(LabelRegion, Option<String>)> = Vec::new();
+    for (i, w) in words.iter().enumerate() {
+        let continues = i > 0 && {
+            let prev = &words[i - 1];
+            w.start - prev.end < SEGMENT_PAUSE_SECS
+                && !prev.text.ends_with(['.', '?', '!'])
+                && w.speaker == prev.speaker
+        };
+        match segments.last_mut() {
+            Some((seg, _)) if continues => {
+                seg.end = seg.end.max(w.end);
+                seg.label.push(' ');
+                seg.label.push_str(&w.text);
+            }
+            _ => segments.push((
+                LabelRegion { start: w.start, end: w.end, label: w.text.clone() },
+                w.speaker.clone(),
+            )),
+        }
+    }
+    segments
+}
+
+fn render_audacity(regions: &[LabelRegion]) -> String {
+    let mut out = String::new();
+    for r in regions {
+        // Labels are tab-separated, so tabs and newlines can't appear in the text
+        let label = r.label.replace(['\t', '\n', '\r'], " ");
+        let _ = writeln!(out, "{:.6}\t{:.6}\t{}", r.start, r.end, label);
+    }
+    out
+}
+
+/// Fill an interval tier: TextGrid intervals must tile [0, xmax] without gaps or overlaps
+fn tile_intervals(regions: &[LabelRegion], xmax: f64) -> Vec<LabelRegion> {
+    let mut tiled = Vec::with_capacity(regions.len() * 2 + 1);
+    let mut cursor = 0.0;
+    for r in regions {
+        let start = r.start.max(cursor);
+        let end = r.end.min(xmax);
+        if end <= start {
+            continue;
+        }
+        if start > cursor {
+            tiled.push(LabelRegion { start: cursor, end: start, label: String::new() });
+        }
+        tiled.push(LabelRegion { start, end, label: r.label.clone() });
+        cursor = end;
+    }
+    if cursor < xmax {
+        tiled.push(LabelRegion { start: cursor, end: xmax, label: String::new() });
+    }
+    tiled
+}
+
+/// Praat long-format TextGrid with one interval tier per (name, regions) pair
+fn render_textgrid(tiers: &[(&str, Vec<LabelRegion>)], xmax: f64) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "File type = \"ooTextFile\"\nObject class = \"TextGrid\"\n");
+    let _ = writeln!(out, "xmin = 0\nxmax = {}\ntiers? <exists>\nsize = {}\nitem []:", xmax, tiers.len());
+    for (t, (name, regions)) in tiers.iter().enumerate() {
+        let intervals = tile_intervals(regions, xmax);
+        let _ = writeln!(out, "    item [{}]:", t + 1);
+        let _ = writeln!(out, "        class = \"IntervalTier\"\n        name = \"{}\"", name);
+        let _ = writeln!(out, "        xmin = 0\n        xmax = {}\n        intervals: size = {}", xmax, intervals.len());
+        for (i, r) in intervals.iter().enumerate() {
+            let _ = writeln!(out, "        intervals [{}]:", i + 1);
+            let _ = writeln!(out, "            xmin = {}\n            xmax = {}", r.start, r.end);
+            let _ = writeln!(out, "            text = \"{}\"", r.label.replace('"', "\"\""));
+        }
+    }
+    out
+}
+
+fn render_plain_text(segments: &[(LabelRegion, Option<String>)]) -> String {
+    let mut out = String::new();
+    for (seg, speaker) in segments {
+        let speaker = speaker.as_deref().map(|s| format!("{}: ", s)).unwrap_or_default();
+        let _ = writeln!(out, "[{}] {}{}", format_timestamp(seg.start, '.'), speaker, seg.label);
+    }
+    out
+}
+
+/// Parse an Audacity label track (`start<TAB>end<TAB>label` per line).
+/// Spectral-selection lines (starting with `\`) are skipped.
+fn parse_audacity_labels(content: &str) -> Result<Vec<LabelRegion>, String> {
+    let mut labels = Vec::new();
+    for (n, line) in content.lines().enumerate() {
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() || line.starts_with('\\') {
+            continue;
+        }
+        let mut parts = line.splitn(3, '\t');
+        let mut time = |name: &str| -> Result<f64, String> {
+            parts
+                .next()
+                .and_then(|t| t.trim().parse::<f64>().ok())
+                .ok_or_else(|| format!("Invalid label on line {}: missing or bad {} time", n + 1, name))
+        };
+        let start = time("start")?;
+        let end = time("end")?;
+        let label = parts.next().unwrap_or("").trim().to_string();
+        labels.push(LabelRegion { start, end: end.max(start), label });
+    }
+    Ok(labels)
+}
+
+/// Write a transcript as an Audacity label track ("audacity"), Praat TextGrid ("textgrid")
+/// or timestamped plain text ("text").
+///
+/// Word timings get the metadata's global offset and per-word adjustments. `level`
+/// picks word or segment labels for Audacity; TextGrid always has both tiers.
+/// `duration` sets the TextGrid end (default: end of the last word).
+#[tauri::command]
+pub async fn export_transcript(
+    metadata: TranscriptionMetadata,
+    output_path: String,
+    format: String,
+    level: Option<String>,
+    duration: Option<f64>,
+) -> Result<TranscriptExportResult, String> {
+    let words = adjusted_words(&metadata);
+    if words.is_empty() {
+        return Err("Transcript has no words to export".to_string());
+    }
+    let segments = group_segments(&words);
+    let word_regions: Vec<LabelRegion> = words
+        .iter()
+        .map(|w| LabelRegion { start: w.start, end: w.end, label: w.text.clone() })
+        .collect();
+
+    let format = format.to_lowercase();
+    let (content, item_count) = match format.as_str() {
+        "audacity" => match level.as_deref().unwrap_or("word") {
+            "word" => (render_audacity(&word_regions), word_regions.len()),
+            "segment" => {
+                let regions: Vec<LabelRegion> = segments.iter().map(|(r, _)| r.clone()).collect();
+                (render_audacity(&regions), regions.len())
+            }
+            other => return Err(format!("Unknown label level '{}': expected \"word\" or \"segment\"", other)),
+        },
+        "textgrid" => {
+            let last_end = words.iter().map(|w| w.end).fold(0.0, f64::max);
+            let xmax = duration.unwrap_or(last_end).max(last_end);
+            let segment_regions: Vec<LabelRegion> = segments.iter().map(|(r, _)| r.clone()).collect();
+            let count = word_regions.len() + segment_regions.len();
+            (render_textgrid(&[("words", word_regions), ("segments", segment_regions)], xmax), count)
+        }
+        "text" => (render_plain_text(&segments), segments.len()),
+        other => {
+            return Err(format!(
+                "Unknown transcript format '{}': expected \"audacity\", \"textgrid\" or \"text\"",
+                other
+            ))
+        }
+    };
+
+    fs::write(&output_path, content)
+        .map_err(|e| format!("Failed to write transcript: {}", e))?;
+
+    log::info!("Exported transcript as {} ({} items) to {}", format, item_count, output_path);
+    Ok(TranscriptExportResult { output_path, format, item_count })
+}
+
+/// Read an Audacity label track back: single-word labels become transcript words,
+/// everything else (point labels, phrases, long regions) comes back as regions.
+#[tauri::command]
+pub async fn import_audacity_labels(path: String) -> Result<AudacityLabelImport, String> {
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read label file: {}", e))?;
+    let labels = parse_audacity_labels(&content)?;
+
+    let mut words = Vec::new();
+    let mut regions = Vec::new();
+    for label in labels {
+        let is_word = !label.label.is_empty()
+            && !label.label.contains(char::is_whitespace)
+            && label.end > label.start
+            && label.end - label.start <= MAX_IMPORTED_WORD_SECS;
+        if is_word {
+            words.push(TranscribedWord {
+                id: uuid::Uuid::new_v4().to_string(),
+                text: label.label,
+                start: label.start,
+                end: label.end,
+                confidence: 1.0,
+                speaker: None,
+            });
+        } else {
+            regions.push(label);
+        }
+    }
+
+    log::info!("Imported {} words and {} regions from {}", words.len(), regions.len(), path);
+    Ok(AudacityLabelImport { words, regions })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::metadata::test_transcribed_word;
+
+    fn region(start: f64, end: f64, label: &str) -> LabelRegion {
+        LabelRegion { start, end, label: label.to_string() }
+    }
+
+    #[test]
+    fn test_group_segments_breaks_on_pauses_and_speakers() {
+        let words: Vec<TranscribedWord> = [
+            ("Hello", 0.0, 0.4, "speaker_1"),
+            ("there.", 0.5, 0.9, "speaker_1"),
+            ("Hi", 1.0, 1.2, "speaker_2"),
+            ("again", 1.2, 1.5, "speaker_2"),
+            ("later", 3.0, 3.4, "speaker_2"),
+        ]
+        .iter()
+        .map(|&(text, start, end, speaker)| TranscribedWord {
+            speaker: Some(speaker.to_string()),
+            ..test_transcribed_word(text, text, start, end)
+        })
+        .collect();
+        let segments = group_segments(&words);
+        let labels: Vec<&str> = segments.iter().map(|(r, _)| r.label.as_str()).collect();
+        assert_eq!(labels, vec!["Hello there.", "Hi again", "later"]);
+        assert_eq!(segments[1].0, region(1.0, 1.5, "Hi again"));
+        assert_eq!(
+            render_plain_text(&segments[..1]),
+            "[00:00:00.000] speaker_1: Hello there.\n"
+        );
+    }
+
+    #[test]
+    fn test_audacity_labels_round_trip() {
+        let regions = vec![region(0.5, 0.75, "hello"), region(1.0, 1.25, "big\tworld")];
+        let text = render_audacity(&regions);
+        assert_eq!(text, "0.500000\t0.750000\thello\n1.000000\t1.250000\tbig world\n");
+
+        let with_spectral = format!("{}\\\t100.0\t2000.0\n", text);
+        let parsed = parse_audacity_labels(&with_spectral).unwrap();
+        assert_eq!(parsed, vec![region(0.5, 0.75, "hello"), region(1.0, 1.25, "big world")]);
+        assert!(parse_audacity_labels("abc\t1.0\tx").is_err());
+    }
+
+    #[test]
+    fn test_textgrid_tiles_intervals() {
+        let tiled = tile_intervals(&[region(0.5, 1.0, "a"), region(0.9, 1.5, "b")], 2.0);
+        assert_eq!(
+            tiled,
+            vec![region(0.0, 0.5, ""), region(0.5, 1.0, "a"), region(1.0, 1.5, "b"), region(1.5, 2.0, "")]
+        );
+
+        let grid = render_textgrid(&[("words", vec![region(0.5, 1.0, "say \"hi\"")])], 2.0);
+        assert!(grid.starts_with("File type = \"ooTextFile\"\nObject class = \"TextGrid\"\n\nxmin = 0\nxmax = 2\n"));
+        assert!(grid.contains("intervals: size = 3"));
+        assert!(grid.contains("text = \"say \"\"hi\"\"\""));
+    }
+}
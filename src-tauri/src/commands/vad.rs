@@ -8,6 +8,9 @@ use symphonia::core::io::MediaSourceStream;
 use symphonia::core::meta::MetadataOptions;
 use symphonia::core::probe::Hint;
 
+use super::cutlist::{self, KeepRange};
+use super::export::{ExportEDL, ExportEDLTrack};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SpeechSegment {
@@ -24,6 +27,14 @@ pub struct VadResult {
     pub silence_segments: Vec<SpeechSegment>,
     pub total_speech_duration: f64,
     pub total_silence_duration: f64,
+    /// RMS level a frame must exceed to start a speech segment
+    pub onset_threshold: f64,
+    /// RMS level a frame must fall below to end a speech segment
+    pub offset_threshold: f64,
+    /// Estimated noise floor RMS (10th percentile of frame energies)
+    pub noise_floor: f64,
+    /// Whether the thresholds were calibrated from the file (`VadOptions::auto_threshold`)
+    pub auto_threshold: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -39,6 +50,10 @@ pub struct VadOptions {
     pub padding: f64,
     /// Minimum silence gap duration in seconds to count as a silence region
     pub min_silence_duration: f64,
+    /// Estimate onset/offset thresholds from the file's energy distribution
+    /// instead of scaling by `energy_threshold`
+    #[serde(default)]
+    pub auto_threshold: bool,
 }
 
 impl Default for VadOptions {
@@ -49,8 +64,100 @@ impl Default for VadOptions {
             frame_size_ms: 30.0,
             padding: 0.15,
             min_silence_duration: 0.3,
+            auto_threshold: false,
+        }
+    }
+}
+
+/// Thresholds picked by `calibrate_thresholds` (all linear RMS)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct VadThresholds {
+    pub onset: f32,
+    pub offset: f32,
+    pub noise_floor: f32,
+    pub speech_level: f32,
+}
+
+/// Minimum gap in dB between the onset threshold and the noise floor
+const MIN_ONSET_MARGIN_DB: f32 = 6.0;
+/// Offset threshold position between noise floor and onset (0 = floor, 1 = onset)
+const OFFSET_HYSTERESIS: f32 = 0.5;
+
+/// Linear amplitude to dBFS (floored at -120 dB)
+pub(crate) fn to_db(rms: f32) -> f32 {
+    20.0 * rms.max(1e-6).log10()
+}
+
+pub(crate) fn from_db(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
+
+/// Estimate speech onset/offset thresholds from per-frame RMS energies.
+///
+/// Frame energies are fitted with a two-class (Otsu) split on a dB histogram:
+/// the noise class sits around the 10th percentile, speech around the 90th.
+/// The onset threshold is the split point (kept at least 6 dB above the floor);
+/// the offset threshold sits halfway between floor and onset so segments don't
+/// chatter on decaying syllables.
+pub(crate) fn calibrate_thresholds(energies: &[f32]) -> VadThresholds {
+    if energies.is_empty() {
+        let onset = from_db(-40.0);
+        return VadThresholds { onset, offset: onset, noise_floor: 0.0, speech_level: 0.0 };
+    }
+
+    let mut db: Vec<f32> = energies.iter().map(|&e| to_db(e)).collect();
+    db.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+    let percentile = |p: f64| db[((db.len() - 1) as f64 * p) as usize];
+    let noise_db = percentile(0.1);
+    let speech_db = percentile(0.9);
+    let lo = percentile(0.01);
+    let hi = percentile(0.99);
+
+    // Otsu split over a 64-bin histogram between the 1st and 99th percentile
+    const BINS: usize = 64;
+    let span = (hi - lo).max(1e-3);
+    let mut hist = [0usize; BINS];
+    for &d in &db {
+        let bin = (((d - lo) / span) * BINS as f32).clamp(0.0, (BINS - 1) as f32) as usize;
+        hist[bin] += 1;
+    }
+    let bin_center = |b: usize| lo + (b as f32 + 0.5) * span / BINS as f32;
+
+    let total = db.len() as f64;
+    let sum_all: f64 = hist.iter().enumerate().map(|(b, &n)| n as f64 * bin_center(b) as f64).sum();
+    let mut weight_bg = 0.0f64;
+    let mut sum_bg = 0.0f64;
+    let mut best_var = -1.0f64;
+    let mut split_db = (noise_db + speech_db) / 2.0;
+    for (b, &count) in hist.iter().enumerate().take(BINS - 1) {
+        weight_bg += count as f64;
+        sum_bg += count as f64 * bin_center(b) as f64;
+        let weight_fg = total - weight_bg;
+        if weight_bg == 0.0 || weight_fg == 0.0 {
+            continue;
+        }
+        let mean_bg = sum_bg / weight_bg;
+        let mean_fg = (sum_all - sum_bg) / weight_fg;
+        let between = weight_bg * weight_fg * (mean_bg - mean_fg).powi(2);
+        if between > best_var {
+            best_var = between;
+            split_db = lo + (b as f32 + 1.0) * span / BINS as f32;
         }
     }
+
+    // Keep the onset clear of the floor, but below the speech level when they're close
+    let onset_db = split_db
+        .max(noise_db + MIN_ONSET_MARGIN_DB)
+        .min(speech_db.max(noise_db + MIN_ONSET_MARGIN_DB));
+    let offset_db = noise_db + (onset_db - noise_db) * OFFSET_HYSTERESIS;
+
+    VadThresholds {
+        onset: from_db(onset_db),
+        offset: from_db(offset_db),
+        noise_floor: from_db(noise_db),
+        speech_level: from_db(speech_db),
+    }
 }
 
 /// Calculate RMS energy of a frame
@@ -183,6 +290,10 @@ pub async fn detect_speech_segments(
             silence_segments: vec![],
             total_speech_duration: 0.0,
             total_silence_duration: 0.0,
+            onset_threshold: 0.0,
+            offset_threshold: 0.0,
+            noise_floor: 0.0,
+            auto_threshold: opts.auto_threshold,
         });
     }
 
@@ -196,14 +307,25 @@ pub async fn detect_speech_segments(
     let peak_idx = (sorted_energies.len() as f64 * 0.95) as usize;
     let peak = sorted_energies.get(peak_idx).copied().unwrap_or(1.0);
 
-    // Adaptive threshold: above noise floor but scaled by user preference
-    let adaptive_threshold = noise_floor + (peak - noise_floor) * opts.energy_threshold as f32;
+    // Auto mode: calibrate onset/offset from the energy distribution (with hysteresis).
+    // Manual mode: single threshold above noise floor, scaled by user preference.
+    let (onset_threshold, offset_threshold, noise_floor) = if opts.auto_threshold {
+        let calibrated = calibrate_thresholds(&all_energies);
+        log::info!(
+            "VAD auto threshold: onset={:.4} offset={:.4} floor={:.4} speech={:.4}",
+            calibrated.onset, calibrated.offset, calibrated.noise_floor, calibrated.speech_level
+        );
+        (calibrated.onset, calibrated.offset, calibrated.noise_floor)
+    } else {
+        let adaptive_threshold = noise_floor + (peak - noise_floor) * opts.energy_threshold as f32;
+        (adaptive_threshold, adaptive_threshold, noise_floor)
+    };
 
     // Calculate ZCR threshold - speech typically has ZCR < 0.4, noise is higher
     // Use median ZCR of high-energy frames as reference
     let high_energy_zcrs: Vec<f32> = all_energies.iter()
         .zip(all_zcrs.iter())
-        .filter(|(e, _)| **e > adaptive_threshold)
+        .filter(|(e, _)| **e > onset_threshold)
         .map(|(_, z)| *z)
         .collect();
 
@@ -220,13 +342,20 @@ pub async fn detect_speech_segments(
     // Second pass: classify frames using both energy and ZCR
     pos = 0;
     let mut frame_idx = 0;
+    let mut in_speech = false;
     while pos + frame_size <= mono_samples.len() {
         let time = pos as f64 / sample_rate;
         let energy = all_energies[frame_idx];
         let zcr = all_zcrs[frame_idx];
 
-        // Speech: high energy AND reasonable ZCR (not too "noisy")
-        let is_speech = energy > adaptive_threshold && zcr < zcr_threshold;
+        // Speech: high energy AND reasonable ZCR (not too "noisy").
+        // With hysteresis, an active segment only ends once energy drops below the offset.
+        let is_speech = if opts.auto_threshold && in_speech {
+            energy >= offset_threshold
+        } else {
+            energy > onset_threshold && zcr < zcr_threshold
+        };
+        in_speech = is_speech;
 
         frame_energies.push((time, energy, is_speech));
         pos += hop_size;
@@ -336,6 +465,10 @@ pub async fn detect_speech_segments(
         silence_segments,
         total_speech_duration: total_speech,
         total_silence_duration: total_silence,
+        onset_threshold: onset_threshold as f64,
+        offset_threshold: offset_threshold as f64,
+        noise_floor: noise_floor as f64,
+        auto_threshold: opts.auto_threshold,
     })
 }
 
@@ -453,3 +586,189 @@ pub async fn export_without_silence(
 
     Ok(())
 }
+
+/// Default crossfade at pause joins in seconds
+const DEFAULT_PAUSE_CROSSFADE: f64 = 0.02;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShortenSilencesResult {
+    /// Cut-list clips into the source file (timeline order), usable with `export_edl`
+    pub clips: Vec<ExportEDLTrack>,
+    /// Rendered WAV, when an output path was given
+    pub output_path: Option<String>,
+    pub original_duration: f64,
+    pub output_duration: f64,
+    pub pauses_shortened: usize,
+}
+
+/// Plan keep ranges that trim every pause longer than `max_pause` down to `max_pause`.
+/// The middle of each long pause is removed so both neighbouring phrases keep
+/// half the pause. Shorter pauses are left untouched.
+/// Returns (keep ranges, number of pauses shortened).
+pub(crate) fn plan_shortened_pauses(
+    silences: &[SpeechSegment],
+    total_duration: f64,
+    max_pause: f64,
+) -> (Vec<KeepRange>, usize) {
+    let max_pause = max_pause.max(0.0);
+    let removed: Vec<KeepRange> = silences
+        .iter()
+        .filter(|s| !s.is_speech && s.end - s.start > max_pause)
+        .map(|s| KeepRange {
+            start: s.start + max_pause / 2.0,
+            end: s.end - max_pause / 2.0,
+        })
+        .collect();
+    let count = removed.len();
+    (cutlist::invert_ranges(&removed, total_duration), count)
+}
+
+/// Shorten pauses to at most `max_pause` seconds instead of removing them.
+/// Returns the cut list for the timeline and optionally renders it to a WAV file.
+#[tauri::command]
+pub async fn shorten_silences(
+    source_path: String,
+    silence_segments: Vec<SpeechSegment>,
+    max_pause: f64,
+    crossfade: Option<f64>,
+    output_path: Option<String>,
+    app: tauri::AppHandle,
+) -> Result<ShortenSilencesResult, String> {
+    let meta = super::audio::get_audio_metadata(source_path.clone()).await?;
+    // Compressed files without a frame count report 0 — fall back to the last known segment end
+    let total_duration = if meta.duration > 0.0 {
+        meta.duration
+    } else {
+        silence_segments.iter().map(|s| s.end).fold(0.0, f64::max)
+    };
+
+    let (keep, pauses_shortened) = plan_shortened_pauses(&silence_segments, total_duration, max_pause);
+    let clips = cutlist::ranges_to_clips(
+        &source_path,
+        &keep,
+        crossfade.unwrap_or(DEFAULT_PAUSE_CROSSFADE),
+        total_duration,
+    );
+    let output_duration = cutlist::clips_duration(&clips);
+
+    log::info!(
+        "Shorten silences: {} pauses > {:.2}s, {:.1}s -> {:.1}s",
+        pauses_shortened, max_pause, total_duration, output_duration
+    );
+
+    let rendered = match output_path {
+        Some(out) => {
+            let edl = ExportEDL {
+                tracks: clips.clone(),
+                output_path: out,
+                format: "wav".to_string(),
+                sample_rate: meta.sample_rate,
+                channels: meta.channels.clamp(1, 2) as u16,
+                mp3_bitrate: None,
+                ogg_quality: None,
+                start_time: 0.0,
+                end_time: output_duration,
+                redaction: None,
+            };
+            let path = tokio::task::spawn_blocking(move || {
+                super::export::export_edl_inner(&edl, &app)
+            }).await.map_err(|e| format!("Render task failed: {}", e))??;
+            Some(path)
+        }
+        None => None,
+    };
+
+    Ok(ShortenSilencesResult {
+        clips,
+        output_path: rendered,
+        original_duration: total_duration,
+        output_duration,
+        pauses_shortened,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic pseudo-random noise in [-1, 1] (LCG — no rand dependency)
+    fn noise(seed: &mut u32) -> f32 {
+        *seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+        (*seed >> 8) as f32 / (1u32 << 23) as f32 - 1.0
+    }
+
+    #[test]
+    fn test_calibrate_separates_noise_and_speech() {
+        let mut seed = 1;
+        let mut energies: Vec<f32> = (0..700).map(|_| 0.002 + noise(&mut seed).abs() * 0.001).collect();
+        energies.extend((0..300).map(|_| 0.1 + noise(&mut seed).abs() * 0.05));
+
+        let t = calibrate_thresholds(&energies);
+        assert!(t.noise_floor < 0.004, "noise floor {}", t.noise_floor);
+        assert!(t.onset > 0.004 && t.onset < 0.1, "onset {}", t.onset);
+        assert!(t.offset < t.onset, "offset {} should be below onset {}", t.offset, t.onset);
+        assert!(t.offset > t.noise_floor, "offset {} should be above floor {}", t.offset, t.noise_floor);
+    }
+
+    #[test]
+    fn test_plan_shortened_pauses_keeps_short_pauses() {
+        let silences = vec![
+            SpeechSegment { start: 2.0, end: 2.3, is_speech: false },
+            SpeechSegment { start: 5.0, end: 7.0, is_speech: false },
+        ];
+        let (keep, count) = plan_shortened_pauses(&silences, 10.0, 0.4);
+        assert_eq!(count, 1);
+        assert_eq!(keep, vec![
+            KeepRange { start: 0.0, end: 5.2 },
+            KeepRange { start: 6.8, end: 10.0 },
+        ]);
+        let kept: f64 = keep.iter().map(|r| r.end - r.start).sum();
+        assert!((kept - 8.4).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_calibrate_empty_input() {
+        let t = calibrate_thresholds(&[]);
+        assert!(t.onset > 0.0);
+        assert_eq!(t.onset, t.offset);
+    }
+
+    /// 1s noise, 1s tone, 1s noise, 1s tone, 1s noise → two speech segments in auto mode
+    #[tokio::test]
+    async fn test_auto_threshold_detects_bursts() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bursts.wav");
+        let sr = 16000u32;
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: sr,
+            bits_per_sample: 32,
+            sample_format: hound::SampleFormat::Float,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        let mut seed = 7;
+        for i in 0..(5 * sr) {
+            let t = i as f32 / sr as f32;
+            let second = i / sr;
+            let s = if second == 1 || second == 3 {
+                (t * 220.0 * 2.0 * std::f32::consts::PI).sin() * 0.3
+            } else {
+                noise(&mut seed) * 0.002
+            };
+            writer.write_sample(s).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let opts = VadOptions { auto_threshold: true, padding: 0.0, ..Default::default() };
+        let result = detect_speech_segments(path.to_string_lossy().to_string(), Some(opts))
+            .await
+            .unwrap();
+
+        assert!(result.auto_threshold);
+        assert!(result.offset_threshold < result.onset_threshold);
+        assert_eq!(result.speech_segments.len(), 2, "segments: {:?}", result.speech_segments);
+        let first = &result.speech_segments[0];
+        assert!((first.start - 1.0).abs() < 0.1 && (first.end - 2.0).abs() < 0.1, "{:?}", first);
+    }
+}
@@ -5,7 +5,7 @@ mod audio_clean;
 mod audio_util;
 mod services;
 
-use commands::{audio, waveform, transcribe, moonshine, export, vad, clean, metadata, recording, import, playback, project};
+use commands::{audio, waveform, transcribe, moonshine, engine, models, live_transcribe, export, vad, clean, metadata, recording, import, playback, project, fillers, diarize, classify, subtitles, transcript_export, text_edit, redact};
 use std::panic;
 use tauri::Manager;
 
@@ -39,8 +39,10 @@ fn main() {
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
         .manage(import::ImportState::new())
+        .manage(transcribe::TranscriptionState::new())
         .manage(playback::PlaybackEngine::new())
         .manage(recording::RecordingManager::new())
+        .manage(live_transcribe::LiveTranscriptionState::new())
         .setup(|app| {
             let app_handle = app.handle().clone();
             // Inject AppHandle into RecordingManager for push-based level emission
@@ -61,6 +63,10 @@ fn main() {
             audio::splice_wav_remove_region,
             waveform::extract_waveform,
             transcribe::transcribe_audio,
+            transcribe::cancel_transcription,
+            transcribe::unload_whisper_model,
+            transcribe::align_transcript,
+            transcribe::find_low_confidence_words,
             transcribe::check_whisper_model,
             transcribe::get_models_directory,
             transcribe::list_available_models,
@@ -73,12 +79,18 @@ fn main() {
             export::export_edl,
             vad::detect_speech_segments,
             vad::export_without_silence,
+            vad::shorten_silences,
+            fillers::detect_filler_words,
+            fillers::build_filler_cut_list,
             clean::clean_audio,
             clean::detect_mains_freq,
             clean::get_temp_audio_path,
             metadata::save_transcription_metadata,
             metadata::load_transcription_metadata,
+            metadata::lookup_transcription_by_content,
             metadata::delete_transcription_metadata,
+            metadata::get_transcription_vocabulary,
+            metadata::set_transcription_vocabulary,
             recording::list_audio_devices,
             recording::list_all_audio_devices,
             recording::get_device_capabilities,
@@ -120,6 +132,25 @@ fn main() {
             moonshine::transcribe_moonshine,
             moonshine::check_moonshine_model,
             moonshine::list_moonshine_models,
+            engine::transcribe,
+            engine::list_transcription_engines,
+            engine::check_transcription_model,
+            models::delete_model,
+            models::get_model_disk_usage,
+            models::import_model,
+            models::set_default_model,
+            models::get_default_models,
+            live_transcribe::start_live_transcription,
+            live_transcribe::stop_live_transcription,
+            live_transcribe::cancel_live_transcription,
+            diarize::diarize_audio,
+            diarize::assign_speakers,
+            classify::classify_audio,
+            subtitles::export_subtitles,
+            transcript_export::export_transcript,
+            transcript_export::import_audacity_labels,
+            text_edit::compile_text_edit,
+            redact::find_redaction_matches,
             import::import_audio_start,
             import::import_audio_cancel,
             import::get_peak_tile,
@@ -1 +1,2 @@
+pub mod model_download;
 pub mod path_service;
@@ -0,0 +1,426 @@
+//! Verified, resumable model downloads.
+//!
+//! Files are downloaded to `<target>.part`, resumed with an HTTP Range request when a
+//! partial file is left over, checked against the expected size and SHA-256, and only
+//! then renamed into place — model discovery never sees an incomplete file. A file
+//! with neither a known size nor a checksum is never renamed into place.
+
+use futures_util::StreamExt;
+use reqwest::header::{CONTENT_RANGE, RANGE};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+
+/// Manifest file name, looked up in the bundled models directory and the user's models directory
+pub const MANIFEST_FILENAME: &str = "model-manifest.json";
+
+/// Known checksum (and optionally size) of a downloadable model file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestEntry {
+    pub filename: String,
+    pub sha256: String,
+    #[serde(default)]
+    pub size: Option<u64>,
+}
+
+/// What the finished file must match; unknown fields aren't checked
+#[derive(Debug, Clone, Default)]
+pub struct ExpectedFile {
+    pub sha256: Option<String>,
+    pub size: Option<u64>,
+}
+
+impl From<&ManifestEntry> for ExpectedFile {
+    fn from(entry: &ManifestEntry) -> Self {
+        Self { sha256: Some(entry.sha256.clone()), size: entry.size }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DownloadOutcome {
+    pub path: PathBuf,
+    pub sha256: String,
+    pub size: u64,
+    /// Bytes already on disk from an earlier, interrupted attempt
+    pub resumed_from: u64,
+}
+
+/// Read a manifest (a JSON array of entries)
+pub fn load_manifest(path: &Path) -> Result<Vec<ManifestEntry>, String> {
+    let json = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read model manifest {:?}: {}", path, e))?;
+    serde_json::from_str(&json).map_err(|e| format!("Invalid model manifest {:?}: {}", path, e))
+}
+
+/// First manifest entry for `filename` across the given manifest files (missing files are skipped)
+pub fn find_manifest_entry(manifests: &[PathBuf], filename: &str) -> Option<ManifestEntry> {
+    manifests
+        .iter()
+        .filter(|p| p.exists())
+        .filter_map(|p| match load_manifest(p) {
+            Ok(entries) => Some(entries),
+            Err(e) => {
+                log::warn!("{}", e);
+                None
+            }
+        })
+        .flatten()
+        .find(|entry| entry.filename == filename)
+}
+
+/// Where an in-progress download of `target` is kept
+pub fn partial_path(target: &Path) -> PathBuf {
+    let mut name = target.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    target.with_file_name(name)
+}
+
+/// SHA-256 (hex) of a file on disk
+pub fn sha256_file(path: &Path) -> Result<String, String> {
+    let mut file = std::fs::File::open(path)
+        .map_err(|e| format!("Failed to open {:?}: {}", path, e))?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 1024 * 1024];
+    loop {
+        let n = file.read(&mut buf).map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Total size from a `Content-Range: bytes a-b/total` (or `bytes */total`) header
+fn content_range_total(headers: &reqwest::header::HeaderMap) -> Option<u64> {
+    headers
+        .get(CONTENT_RANGE)?
+        .to_str()
+        .ok()?
+        .rsplit('/')
+        .next()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// First byte of a `Content-Range: bytes a-b/total` header
+fn content_range_start(headers: &reqwest::header::HeaderMap) -> Option<u64> {
+    let value = headers.get(CONTENT_RANGE)?.to_str().ok()?;
+    value.strip_prefix("bytes ")?.split('-').next()?.trim().parse().ok()
+}
+
+/// A finished download must match a known size; with no size known (no manifest
+/// size and no Content-Length) only a checksum can vouch for it
+fn check_size(size: u64, expected_size: Option<u64>, has_checksum: bool) -> Result<(), String> {
+    match expected_size {
+        Some(expected) if size != expected => Err(format!("Downloaded {} bytes but expected {}", size, expected)),
+        None if !has_checksum => Err(format!(
+            "Downloaded {} bytes but neither the size nor a checksum is known, so the download can't be verified",
+            size
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Download `url` to `target`, resuming a previous partial download if there is one.
+///
+/// `on_progress(downloaded, total)` is called as data arrives. An interrupted transfer
+/// leaves the `.part` file for the next attempt; a size or checksum mismatch deletes it.
+pub async fn download_verified<F: FnMut(u64, Option<u64>)>(
+    client: &reqwest::Client,
+    url: &str,
+    target: &Path,
+    expected: &ExpectedFile,
+    mut on_progress: F,
+) -> Result<DownloadOutcome, String> {
+    let part = partial_path(target);
+    let mut restarted = false;
+
+    let (resumed_from, total) = loop {
+        let mut offset = tokio::fs::metadata(&part).await.map(|m| m.len()).unwrap_or(0);
+        if matches!(expected.size, Some(size) if offset > size) {
+            log::warn!("Partial download {:?} is larger than expected, starting over", part);
+            let _ = tokio::fs::remove_file(&part).await;
+            offset = 0;
+        }
+
+        let mut request = client.get(url);
+        if offset > 0 {
+            log::info!("Resuming download of {} at byte {}", url, offset);
+            request = request.header(RANGE, format!("bytes={}-", offset));
+        }
+        let response = request.send().await.map_err(|e| format!("Failed to start download: {}", e))?;
+        let status = response.status();
+
+        if status == StatusCode::RANGE_NOT_SATISFIABLE && offset > 0 {
+            // Either the partial file is already complete, or it doesn't match the remote file
+            if content_range_total(response.headers()) == Some(offset) {
+                break (offset, Some(offset));
+            }
+            if restarted {
+                return Err(format!("Download failed with status: {}", status));
+            }
+            let _ = tokio::fs::remove_file(&part).await;
+            restarted = true;
+            continue;
+        }
+        if !status.is_success() {
+            return Err(format!("Download failed with status: {}", status));
+        }
+
+        // 206 continues the partial file; anything else is the whole file from the start
+        let resume = status == StatusCode::PARTIAL_CONTENT && offset > 0;
+        if resume && content_range_start(response.headers()) != Some(offset) {
+            return Err(format!(
+                "Server resumed at the wrong offset ({:?}, expected {})",
+                response.headers().get(CONTENT_RANGE), offset
+            ));
+        }
+        let (start, total) = if resume {
+            (offset, content_range_total(response.headers()))
+        } else {
+            (0, response.content_length())
+        };
+
+        let mut file = if resume {
+            tokio::fs::OpenOptions::new().append(true).open(&part).await
+        } else {
+            tokio::fs::File::create(&part).await
+        }
+        .map_err(|e| format!("Failed to open {:?}: {}", part, e))?;
+
+        let mut downloaded = start;
+        on_progress(downloaded, total);
+        let mut stream = response.bytes_stream();
+        let mut stream_error = None;
+        while let Some(chunk) = stream.next().await {
+            match chunk {
+                Ok(bytes) => {
+                    file.write_all(&bytes).await.map_err(|e| format!("Failed to write {:?}: {}", part, e))?;
+                    downloaded += bytes.len() as u64;
+                    on_progress(downloaded, total);
+                }
+                Err(e) => {
+                    stream_error = Some(e);
+                    break;
+                }
+            }
+        }
+        file.flush().await.map_err(|e| format!("Failed to write {:?}: {}", part, e))?;
+        file.sync_all().await.map_err(|e| format!("Failed to write {:?}: {}", part, e))?;
+        drop(file);
+
+        if let Some(e) = stream_error {
+            return Err(format!("Download interrupted after {} bytes ({}); it will resume on retry", downloaded, e));
+        }
+        if matches!(total, Some(t) if downloaded < t) {
+            return Err(format!(
+                "Download interrupted after {} of {} bytes; it will resume on retry",
+                downloaded, total.unwrap_or(0)
+            ));
+        }
+        break (if resume { offset } else { 0 }, total);
+    };
+
+    let size = tokio::fs::metadata(&part).await.map(|m| m.len()).unwrap_or(0);
+    if let Err(e) = check_size(size, expected.size.or(total), expected.sha256.is_some()) {
+        let _ = tokio::fs::remove_file(&part).await;
+        return Err(format!("{}; the partial file was removed", e));
+    }
+
+    let hash_path = part.clone();
+    let sha256 = tokio::task::spawn_blocking(move || sha256_file(&hash_path))
+        .await
+        .map_err(|e| format!("Checksum task failed: {}", e))??;
+    match &expected.sha256 {
+        Some(want) if !want.trim().eq_ignore_ascii_case(&sha256) => {
+            let _ = tokio::fs::remove_file(&part).await;
+            return Err(format!(
+                "Checksum mismatch for {:?}: expected {}, got {}; the download was discarded",
+                target.file_name().unwrap_or_default(), want.trim(), sha256
+            ));
+        }
+        Some(_) => log::info!("Verified SHA-256 of {:?}", target),
+        None => log::warn!("No checksum known for {:?} (SHA-256 {}); size checked only", target, sha256),
+    }
+
+    tokio::fs::rename(&part, target)
+        .await
+        .map_err(|e| format!("Failed to move download into place: {}", e))?;
+
+    Ok(DownloadOutcome { path: target.to_path_buf(), sha256, size, resumed_from })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Minimal HTTP/1.1 file server. Records each request's Range start; honours
+    /// ranges if `ranges` is set; cuts the first response off after `cut_after` bytes.
+    struct TestServer {
+        url: String,
+        requests: Arc<Mutex<Vec<Option<u64>>>>,
+    }
+
+    async fn serve(body: Vec<u8>, ranges: bool, cut_after: Option<usize>) -> TestServer {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/model.bin", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let seen = requests.clone();
+        let body = Arc::new(body);
+
+        tokio::spawn(async move {
+            let mut cut = cut_after;
+            loop {
+                let Ok((mut socket, _)) = listener.accept().await else { return };
+                let mut head = Vec::new();
+                let mut buf = [0u8; 1024];
+                while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+                    match socket.read(&mut buf).await {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => head.extend_from_slice(&buf[..n]),
+                    }
+                }
+                let head = String::from_utf8_lossy(&head).to_lowercase();
+                let range_start: Option<u64> = head
+                    .lines()
+                    .find_map(|l| l.strip_prefix("range: bytes="))
+                    .and_then(|r| r.trim().trim_end_matches('-').parse().ok());
+                seen.lock().unwrap().push(range_start);
+
+                let len = body.len() as u64;
+                let (header, start) = match range_start.filter(|_| ranges) {
+                    Some(start) if start >= len => (
+                        format!("HTTP/1.1 416 Range Not Satisfiable\r\nContent-Range: bytes */{}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", len),
+                        len,
+                    ),
+                    Some(start) => (
+                        format!(
+                            "HTTP/1.1 206 Partial Content\r\nContent-Length: {}\r\nContent-Range: bytes {}-{}/{}\r\nConnection: close\r\n\r\n",
+                            len - start, start, len - 1, len
+                        ),
+                        start,
+                    ),
+                    None => (format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", len), 0),
+                };
+                let mut payload = &body[start as usize..];
+                if let Some(n) = cut.take() {
+                    payload = &payload[..n.min(payload.len())];
+                }
+                let _ = socket.write_all(header.as_bytes()).await;
+                let _ = socket.write_all(payload).await;
+                let _ = socket.shutdown().await;
+            }
+        });
+        TestServer { url, requests }
+    }
+
+    fn test_body() -> Vec<u8> {
+        (0..200_000u32).map(|i| (i * 7 % 251) as u8).collect()
+    }
+
+    fn sha256_hex(data: &[u8]) -> String {
+        format!("{:x}", Sha256::digest(data))
+    }
+
+    #[tokio::test]
+    async fn test_download_verifies_and_renames() {
+        let body = test_body();
+        let server = serve(body.clone(), true, None).await;
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("model.bin");
+        let expected = ExpectedFile { sha256: Some(sha256_hex(&body).to_uppercase()), size: Some(body.len() as u64) };
+
+        let mut last_progress = (0, None);
+        let outcome = download_verified(&reqwest::Client::new(), &server.url, &target, &expected, |d, t| {
+            last_progress = (d, t)
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(std::fs::read(&target).unwrap(), body);
+        assert!(!partial_path(&target).exists());
+        assert_eq!(outcome.resumed_from, 0);
+        assert_eq!(last_progress, (body.len() as u64, Some(body.len() as u64)));
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_download_resumes_with_range() {
+        let body = test_body();
+        let server = serve(body.clone(), true, Some(50_000)).await;
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("model.bin");
+        let expected = ExpectedFile { sha256: Some(sha256_hex(&body)), size: None };
+        let client = reqwest::Client::new();
+
+        let err = download_verified(&client, &server.url, &target, &expected, |_, _| {}).await.unwrap_err();
+        assert!(err.contains("resume on retry"), "{}", err);
+        assert!(!target.exists(), "partial file must not be registered");
+        assert_eq!(std::fs::metadata(partial_path(&target)).unwrap().len(), 50_000);
+
+        let outcome = download_verified(&client, &server.url, &target, &expected, |_, _| {}).await.unwrap();
+        assert_eq!(outcome.resumed_from, 50_000);
+        assert_eq!(std::fs::read(&target).unwrap(), body);
+        assert_eq!(*server.requests.lock().unwrap(), vec![None, Some(50_000)]);
+    }
+
+    #[tokio::test]
+    async fn test_server_without_range_support_restarts() {
+        let body = test_body();
+        let server = serve(body.clone(), false, None).await;
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("model.bin");
+        std::fs::write(partial_path(&target), b"stale bytes from another file").unwrap();
+
+        let outcome = download_verified(&reqwest::Client::new(), &server.url, &target, &ExpectedFile::default(), |_, _| {})
+            .await
+            .unwrap();
+        assert_eq!(outcome.resumed_from, 0);
+        assert_eq!(outcome.sha256, sha256_hex(&body));
+        assert_eq!(std::fs::read(&target).unwrap(), body);
+    }
+
+    #[tokio::test]
+    async fn test_checksum_mismatch_discards_download() {
+        let body = test_body();
+        let server = serve(body, true, None).await;
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("model.bin");
+        let expected = ExpectedFile { sha256: Some(sha256_hex(b"something else")), size: None };
+
+        let err = download_verified(&reqwest::Client::new(), &server.url, &target, &expected, |_, _| {})
+            .await
+            .unwrap_err();
+        assert!(err.contains("Checksum mismatch"), "{}", err);
+        assert!(!target.exists());
+        assert!(!partial_path(&target).exists());
+    }
+
+    #[test]
+    fn test_unverifiable_download_is_refused() {
+        assert!(check_size(10, Some(10), false).is_ok());
+        assert!(check_size(10, None, true).is_ok());
+        assert!(check_size(9, Some(10), true).unwrap_err().contains("expected 10"));
+        assert!(check_size(10, None, false).unwrap_err().contains("can't be verified"));
+    }
+
+    #[test]
+    fn test_manifest_lookup() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = dir.path().join(MANIFEST_FILENAME);
+        std::fs::write(&manifest, r#"[{"filename": "ggml-tiny.bin", "sha256": "abc123", "size": 42}]"#).unwrap();
+        let missing = dir.path().join("nope.json");
+
+        let entry = find_manifest_entry(&[missing, manifest.clone()], "ggml-tiny.bin").unwrap();
+        assert_eq!((entry.sha256.as_str(), entry.size), ("abc123", Some(42)));
+        assert!(find_manifest_entry(&[manifest], "ggml-base.bin").is_none());
+    }
+}
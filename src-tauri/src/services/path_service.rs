@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::OnceLock;
 use tauri::{AppHandle, Manager};
 use thiserror::Error;
@@ -88,3 +88,24 @@ pub fn get_models_dir() -> Result<PathBuf, String> {
 
     Ok(models_dir)
 }
+
+/// Model root directories in search order, each with a label for logging: the
+/// user's custom path, bundled resources (release builds), next to the executable
+/// (Flatpak, portable installs), the dev resources folder, then app data.
+pub fn model_search_dirs(custom_path: Option<&str>, resource_dir: Option<&Path>) -> Vec<(&'static str, PathBuf)> {
+    let mut dirs = Vec::new();
+    if let Some(custom) = custom_path.filter(|p| !p.is_empty()) {
+        dirs.push(("custom path", PathBuf::from(custom)));
+    }
+    if let Some(res_dir) = resource_dir {
+        dirs.push(("bundled resources", res_dir.join("models")));
+    }
+    if let Some(exe_dir) = std::env::current_exe().ok().and_then(|e| e.parent().map(Path::to_path_buf)) {
+        dirs.push(("next to executable", exe_dir.join("models")));
+    }
+    dirs.push(("dev path", PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("resources").join("models")));
+    if let Ok(models_dir) = get_models_dir() {
+        dirs.push(("app data", models_dir));
+    }
+    dirs
+}